@@ -1,5 +1,8 @@
-use libmpv2::{events::*, *};
-use serde_json::Value;
+use libmpv2::{
+    cache::{BufferingMonitor, CacheState},
+    events::*,
+    *,
+};
 use std::{env, thread, time::Duration};
 
 const VIDEO_URL: &str = "https://www.youtube.com/watch?v=VLnWf1sQkjY";
@@ -19,9 +22,10 @@ fn main() -> Result<()> {
 
     mpv.disable_deprecated_events()?;
     mpv.observe_property("volume", Format::Int64, 0)?;
-    mpv.observe_property("demuxer-cache-state", Format::String, 0)?;
+    mpv.observe_property("demuxer-cache-state", Format::Node, 0)?;
 
     let mut mpv_client = mpv.create_client(None)?;
+    let mut buffering = BufferingMonitor::observe(&mpv_client, 1)?;
 
     crossbeam::scope(|scope| {
         scope.spawn(|_| {
@@ -40,19 +44,34 @@ fn main() -> Result<()> {
             loop {
                 let ev = mpv_client.wait_event(600.).unwrap_or(Err(Error::Null));
 
+                if let Ok(ev) = &ev
+                    && let Some(change) = buffering.handle_event(ev)
+                {
+                    println!("Buffering: {:?}", change);
+                    continue;
+                }
+
                 match ev {
-                    Ok(Event::EndFile(r)) => {
-                        println!("Exiting! Reason: {:?}", r);
+                    Ok(Event::EndFile { reason, .. }) => {
+                        println!("Exiting! Reason: {:?}", reason);
                         break;
                     }
 
                     Ok(Event::PropertyChange {
                         name: "demuxer-cache-state",
-                        change: PropertyData::Str(r),
+                        change: PropertyData::Node(node),
                         ..
                     }) => {
-                        let ranges = seekable_ranges(r);
-                        println!("Seekable ranges updated: {:?}", ranges);
+                        let state = CacheState::from_node(&node).unwrap();
+                        let ranges: Vec<_> = state
+                            .seekable_ranges
+                            .iter()
+                            .map(|range| (range.start, range.end))
+                            .collect();
+                        println!(
+                            "Seekable ranges updated: {:?}, {:?}s ahead",
+                            ranges, state.cache_duration
+                        );
                     }
                     Ok(e) => println!("Event triggered: {:?}", e),
                     Err(e) => println!("Event errored: {:?}", e),
@@ -63,18 +82,3 @@ fn main() -> Result<()> {
     .unwrap();
     Ok(())
 }
-
-fn seekable_ranges(demuxer_cache_state: &str) -> Vec<(f64, f64)> {
-    let mut res = Vec::new();
-
-    let v: Value = serde_json::from_str(demuxer_cache_state).unwrap();
-    let ranges = v["seekable-ranges"].as_array().unwrap();
-
-    for val in ranges {
-        let range = val.as_object().unwrap();
-        let start = range.get("start").unwrap().as_f64().unwrap();
-        let end = range.get("end").unwrap().as_f64().unwrap();
-        res.push((start, end));
-    }
-    res
-}
@@ -76,7 +76,7 @@ fn main() {
                     }
                     UserEvent::MpvEventAvailable => loop {
                         match mpv.wait_event(0.0) {
-                            Some(Ok(libmpv2::events::Event::EndFile(_))) => {
+                            Some(Ok(libmpv2::events::Event::EndFile { .. })) => {
                                 break 'render;
                             }
                             Some(Ok(mpv_event)) => {
@@ -6,14 +6,43 @@ macro_rules! mpv_cstr_to_str {
     };
 }
 
+mod callback;
 mod errors;
 
+pub mod analysis;
+pub mod audio_devices;
+pub mod audio_tap;
+pub mod cache;
+/// Offscreen frame capture
+#[cfg(feature = "render")]
+pub mod capture;
+pub mod chapters;
+pub mod clip;
+pub mod commands;
+/// Rendering several instances into one canvas
+#[cfg(feature = "render")]
+pub mod compositor;
+pub mod encoder;
 /// Event handling
 pub mod events;
+pub mod filters;
+/// Owned video frames
+pub mod frame;
+pub mod metadata;
+/// Structured values
+pub mod mpv_node;
+pub mod playlist;
+pub mod pool;
+pub mod probe;
+pub mod properties;
 pub mod protocol;
 /// Custom rendering
 #[cfg(feature = "render")]
 pub mod render;
+#[cfg(feature = "render")]
+pub mod thumbnails;
+pub mod track_policy;
+pub mod tracks;
 
 pub use self::errors::*;
 use super::*;
@@ -323,7 +352,7 @@ impl Mpv {
     ///     - script
     ///     - player-operation-mode
     ///     - input-app-events (OSX)
-    /// 2. all encoding mode options
+    /// 2. all encoding mode options, see [`Encoder`](crate::encoder::Encoder)
     ///
     /// Will return `Err` if out of memory or LC_NUMERIC is not set to "C" or
     /// `initializer` fails.
@@ -358,6 +387,7 @@ impl Mpv {
     }
 
     pub fn create_client(&self, name: Option<&str>) -> Result<Mpv> {
+        callback::assert_outside("Mpv::create_client");
         let mpv_handle = unsafe {
             libmpv2_sys::mpv_create_client(
                 self.ctx.as_ptr(),
@@ -380,6 +410,7 @@ impl Mpv {
     /// Send a command to the player. Commands are the same as those used in
     /// input.conf.
     pub fn command(&self, name: &str, args: &[&str]) -> Result<()> {
+        callback::assert_outside("Mpv::command");
         let mut cstr_args: Vec<CString> = Vec::with_capacity(args.len() + 1);
         cstr_args.push(CString::new(name)?);
 
@@ -402,6 +433,7 @@ impl Mpv {
     /// Will return `Err` If the format `T` doesn't match with the internal format of
     /// the property and it also fails to convert to `T`.
     pub fn set_property<T: SetData>(&self, name: &str, data: T) -> Result<()> {
+        callback::assert_outside("Mpv::set_property");
         let name = CString::new(name)?;
         let format = T::get_format().as_mpv_format() as _;
         data.call_as_c_void(|ptr| {
@@ -417,6 +449,7 @@ impl Mpv {
     /// Will return `Err` If the format `T` doesn't match with the internal format of
     /// the property and it also fails to convert to `T`.
     pub fn get_property<T: GetData>(&self, name: &str) -> Result<T> {
+        callback::assert_outside("Mpv::get_property");
         let name = CString::new(name)?;
 
         let format = T::get_format().as_mpv_format() as _;
@@ -440,11 +473,13 @@ impl Mpv {
     ///
     /// Safe to be called from mpv render API threads.
     pub fn get_time_ns(&self) -> i64 {
+        callback::assert_outside("Mpv::get_time_ns");
         unsafe { libmpv2_sys::mpv_get_time_ns(self.ctx.as_ptr()) }
     }
 
     /// Same as get_time_ns but in microseconds.
     pub fn get_time_us(&self) -> i64 {
+        callback::assert_outside("Mpv::get_time_us");
         unsafe { libmpv2_sys::mpv_get_time_us(self.ctx.as_ptr()) }
     }
 }
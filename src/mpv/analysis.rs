@@ -0,0 +1,300 @@
+//! Measuring files with FFmpeg's analysis filters.
+//!
+//! The functions play a file in a headless instance as fast as possible with
+//! labelled lavfi filters inserted, and read the results from the metadata the
+//! filters export.
+
+use crate::commands::{LoadFile, Stop};
+use crate::events::{Event, PropertyData};
+use crate::metadata::{Metadata, MetadataSource};
+use crate::{Error, Format, Mpv, Result, mpv_error};
+
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Peak and RMS level of a channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelStats {
+    /// RMS level in dBFS.
+    pub rms: f64,
+    /// Sample peak in dBFS.
+    pub peak: f64,
+}
+
+/// The loudness of a file, as returned by [`analyze_audio`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoudnessReport {
+    /// Integrated loudness in LUFS, as defined by EBU R 128.
+    pub integrated: f64,
+    /// Loudness range in LU.
+    pub range: f64,
+    /// Highest true peak of all channels in dBTP.
+    pub true_peak: f64,
+    /// Statistics of each channel, in channel order.
+    pub channels: Vec<ChannelStats>,
+}
+
+impl LoudnessReport {
+    /// The gain in dB that brings the integrated loudness to `target` LUFS,
+    /// e.g. -23 for broadcast or -14 for streaming, without raising the true
+    /// peak above `ceiling` dBTP.
+    pub fn normalization_gain(&self, target: f64, ceiling: f64) -> f64 {
+        (target - self.integrated).min(ceiling - self.true_peak)
+    }
+}
+
+// Read a numeric tag, as formatted by FFmpeg. Silence is reported as `-inf`.
+fn number(metadata: &Metadata, key: &str) -> Result<f64> {
+    metadata
+        .get(key)
+        .ok_or_else(|| Error::Conversion(format!("missing filter metadata {}", key)))?
+        .trim()
+        .parse()
+        .map_err(|_| Error::Conversion(format!("invalid filter metadata {}", key)))
+}
+
+// Play `path` headless and as fast as possible with `options` set, until it
+// reaches its end. The file stays loaded, so the metadata of its filters can
+// be read.
+pub(crate) fn play_through(path: &Path, options: &[(&str, &str)]) -> Result<Mpv> {
+    let path = path
+        .to_str()
+        .ok_or(Error::Raw(mpv_error::InvalidParameter))?;
+
+    let mut mpv = Mpv::with_initializer(|init| {
+        for (name, value) in [
+            ("config", "no"),
+            ("load-scripts", "no"),
+            ("ytdl", "no"),
+            ("sid", "no"),
+            ("vo", "null"),
+            ("ao", "null"),
+            ("ao-null-untimed", "yes"),
+            ("untimed", "yes"),
+            // Stay on the last frame at the end, with the filters still in
+            // place.
+            ("keep-open", "yes"),
+        ] {
+            init.set_property(name, value)?;
+        }
+        for (name, value) in options {
+            init.set_property(name, *value)?;
+        }
+        Ok(())
+    })?;
+    mpv.observe_property("eof-reached", Format::Flag, 0)?;
+    mpv.execute(&LoadFile::new(path))?;
+
+    loop {
+        match mpv.wait_event(-1.) {
+            Some(Ok(Event::PropertyChange {
+                name: "eof-reached",
+                change: PropertyData::Flag(true),
+                ..
+            })) => return Ok(mpv),
+            Some(Ok(Event::EndFile { error, .. })) => {
+                return Err(Error::Loadfile {
+                    error: Rc::new(error.unwrap_or(Error::Raw(mpv_error::NothingToPlay))),
+                });
+            }
+            Some(Ok(Event::Shutdown)) => {
+                return Err(Error::Loadfile {
+                    error: Rc::new(Error::Raw(mpv_error::NothingToPlay)),
+                });
+            }
+            Some(Err(e)) => return Err(Error::Loadfile { error: Rc::new(e) }),
+            Some(Ok(_)) | None => {}
+        }
+    }
+}
+
+/// Measure the loudness of the audio of `path` with the `ebur128` and `astats`
+/// filters.
+///
+/// Returns `Error::Loadfile` if the file can't be played or has no audio.
+pub fn analyze_audio<P: AsRef<Path>>(path: P) -> Result<LoudnessReport> {
+    let mpv = play_through(
+        path.as_ref(),
+        &[
+            ("vid", "no"),
+            (
+                "af",
+                "@loudness:lavfi=[ebur128=peak=true:metadata=1],@stats:lavfi=[astats=metadata=1]",
+            ),
+        ],
+    )?;
+    let loudness = mpv.metadata(&MetadataSource::AudioFilter(String::from("loudness")))?;
+    let stats = mpv.metadata(&MetadataSource::AudioFilter(String::from("stats")))?;
+
+    let channels = (1..)
+        .map_while(|channel| {
+            let key = |stat| format!("lavfi.astats.{}.{}", channel, stat);
+            Some(ChannelStats {
+                rms: number(&stats, &key("RMS_level")).ok()?,
+                peak: number(&stats, &key("Peak_level")).ok()?,
+            })
+        })
+        .collect();
+    Ok(LoudnessReport {
+        integrated: number(&loudness, "lavfi.r128.I")?,
+        range: number(&loudness, "lavfi.r128.LRA")?,
+        true_peak: number(&loudness, "lavfi.r128.true_peak")?,
+        channels,
+    })
+}
+
+/// A cut detected by [`detect_scenes`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scene {
+    /// Time of the first frame of the new scene, in seconds.
+    pub time: f64,
+    /// How different the frame is from the previous one, from 0 to 1.
+    pub score: f64,
+}
+
+/// A time range in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Segment {
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+// Distinguishes the metadata files of several analyses in one process.
+static PRINT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+// Escape `s` for use as a filter option value inside a filter graph, which
+// unescapes it twice.
+fn lavfi_escape(s: &str) -> String {
+    let escape = |s: &str, special: &[char]| {
+        let mut escaped = String::with_capacity(s.len());
+        for c in s.chars() {
+            if c == '\\' || special.contains(&c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    };
+    escape(&escape(s, &['\'', ':']), &['\'', '[', ']', ',', ';'])
+}
+
+// The frames printed by the `metadata` filter in `print` mode: their time and
+// metadata.
+fn parse_printed(text: &str) -> Vec<(f64, Vec<(&str, &str)>)> {
+    let mut frames: Vec<(f64, Vec<_>)> = Vec::new();
+    for line in text.lines() {
+        if line.starts_with("frame:") {
+            let time = line
+                .split_whitespace()
+                .find_map(|field| field.strip_prefix("pts_time:"))
+                .and_then(|time| time.parse().ok())
+                .unwrap_or(f64::NAN);
+            frames.push((time, Vec::new()));
+        } else if let (Some((key, value)), Some((_, entries))) =
+            (line.split_once('='), frames.last_mut())
+        {
+            entries.push((key, value));
+        }
+    }
+    frames
+}
+
+// Play the video of `path` through `filter`, followed by a `metadata` filter
+// printing the metadata of every frame to a file, and return what it printed.
+//
+// Observing `vf-metadata` would miss frames, as they are decoded faster than
+// the changes can be read.
+fn print_video_metadata(path: &Path, filter: &str) -> Result<(String, Option<f64>)> {
+    let print = std::env::temp_dir().join(format!(
+        "libmpv2-metadata-{}-{}.txt",
+        std::process::id(),
+        PRINT_COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let print_str = print
+        .to_str()
+        .ok_or(Error::Raw(mpv_error::InvalidParameter))?;
+    let vf = format!(
+        "@analysis:lavfi=[{},metadata=mode=print:file={}]",
+        filter,
+        lavfi_escape(print_str)
+    );
+
+    let result = play_through(path, &[("aid", "no"), ("vf", &vf)]).and_then(|mut mpv| {
+        let duration = mpv.get_property("duration").ok();
+        // The filter writes its file when it is destroyed, which happens
+        // before the end of the file is reported. `eof-reached` becoming
+        // unavailable would be reported first, as `None`.
+        mpv.unobserve_property(0)?;
+        mpv.execute(&Stop::default())?;
+        while !matches!(
+            mpv.wait_event(-1.),
+            Some(Ok(Event::EndFile { .. })) | Some(Ok(Event::Shutdown))
+        ) {}
+        Ok((fs::read_to_string(&print)?, duration))
+    });
+    let _ = fs::remove_file(&print);
+    result
+}
+
+/// Detect cuts in the video of `path` with the `select` filter's scene score.
+/// Frames scoring above `threshold`, from 0 to 1, start a new scene; 0.3 to 0.4
+/// works for most content.
+///
+/// Returns `Error::Loadfile` if the file can't be played or has no video.
+pub fn detect_scenes<P: AsRef<Path>>(path: P, threshold: f64) -> Result<Vec<Scene>> {
+    let (printed, _) =
+        print_video_metadata(path.as_ref(), &format!("select=gt(scene\\,{})", threshold))?;
+    Ok(parse_printed(&printed)
+        .into_iter()
+        .filter_map(|(time, entries)| {
+            let score = entries
+                .iter()
+                .find(|(key, _)| *key == "lavfi.scene_score")?
+                .1
+                .parse()
+                .ok()?;
+            Some(Scene { time, score })
+        })
+        .collect())
+}
+
+/// Detect black parts of the video of `path` with the `blackdetect` filter,
+/// e.g. to find the end of an intro. Parts shorter than 0.1 seconds are
+/// ignored.
+///
+/// Returns `Error::Loadfile` if the file can't be played or has no video.
+pub fn detect_black<P: AsRef<Path>>(path: P) -> Result<Vec<Segment>> {
+    let (printed, duration) = print_video_metadata(path.as_ref(), "blackdetect=d=0.1:pix_th=0.1")?;
+
+    let mut segments = Vec::new();
+    let mut start = None;
+    for (_, entries) in parse_printed(&printed) {
+        for (key, value) in entries {
+            let Ok(time) = value.parse::<f64>() else {
+                continue;
+            };
+            match key {
+                "lavfi.black_start" => start = Some(time),
+                "lavfi.black_end" => {
+                    if let Some(start) = start.take() {
+                        segments.push(Segment { start, end: time });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    // A black part lasting until the end is not ended by a frame.
+    if let (Some(start), Some(end)) = (start, duration) {
+        segments.push(Segment { start, end });
+    }
+    Ok(segments)
+}
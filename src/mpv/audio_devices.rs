@@ -0,0 +1,147 @@
+//! Audio output devices.
+
+use crate::events::{Event, PropertyData};
+use crate::mpv_node::MpvNode;
+use crate::{Error, Format, Mpv, Result, mpv_error};
+
+/// An entry of the `audio-device-list` property.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AudioDevice {
+    /// Name used to select the device with `audio-device`, e.g.
+    /// `pulse/alsa_output.usb-headset`.
+    pub name: String,
+    /// Human readable description, for display.
+    pub description: String,
+}
+
+impl AudioDevice {
+    /// Decode an entry of `audio-device-list` read as a node.
+    pub fn from_node(node: &MpvNode) -> Result<AudioDevice> {
+        let name = node
+            .get("name")
+            .and_then(MpvNode::str)
+            .ok_or_else(|| Error::Conversion(String::from("audio device without a name")))?;
+        Ok(AudioDevice {
+            name: name.to_owned(),
+            description: node
+                .get("description")
+                .and_then(MpvNode::str)
+                .unwrap_or(name)
+                .to_owned(),
+        })
+    }
+
+    /// Whether this is the `auto` entry, which lets the audio output choose.
+    pub fn is_auto(&self) -> bool {
+        self.name == "auto"
+    }
+}
+
+fn decode_list(node: &MpvNode) -> Result<Vec<AudioDevice>> {
+    node.array()
+        .ok_or_else(|| Error::Conversion(String::from("audio device list is not an array")))?
+        .iter()
+        .map(AudioDevice::from_node)
+        .collect()
+}
+
+impl Mpv {
+    /// The devices of all audio outputs, starting with `auto`.
+    pub fn audio_devices(&self) -> Result<Vec<AudioDevice>> {
+        decode_list(&self.get_property::<MpvNode>("audio-device-list")?)
+    }
+
+    /// Name of the selected audio device.
+    pub fn audio_device(&self) -> Result<String> {
+        self.get_property("audio-device")
+    }
+
+    /// Select the audio device `name`.
+    ///
+    /// Returns `Error::Raw(mpv_error::InvalidParameter)` if there is no such
+    /// device, as mpv would accept the name and fail only when audio starts.
+    pub fn set_audio_device(&self, name: &str) -> Result<()> {
+        if !self
+            .audio_devices()?
+            .iter()
+            .any(|device| device.name == name)
+        {
+            return Err(Error::Raw(mpv_error::InvalidParameter));
+        }
+        self.set_property("audio-device", name)
+    }
+}
+
+/// A change of the device list, reported by [`AudioDeviceWatcher`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DeviceListChange {
+    /// The new list.
+    pub devices: Vec<AudioDevice>,
+    /// Devices that weren't in the previous list, e.g. a plugged in headset.
+    pub added: Vec<AudioDevice>,
+    /// Devices of the previous list that are gone.
+    pub removed: Vec<AudioDevice>,
+}
+
+/// Observes `audio-device-list` and reports which devices come and go.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AudioDeviceWatcher {
+    reply_userdata: u64,
+    devices: Vec<AudioDevice>,
+}
+
+impl AudioDeviceWatcher {
+    /// Observe `audio-device-list` on `mpv` with `reply_userdata` as observer
+    /// id.
+    pub fn observe(mpv: &Mpv, reply_userdata: u64) -> Result<AudioDeviceWatcher> {
+        mpv.observe_property("audio-device-list", Format::Node, reply_userdata)?;
+        Ok(AudioDeviceWatcher {
+            reply_userdata,
+            devices: mpv.audio_devices()?,
+        })
+    }
+
+    /// Update the list from `event`. Returns the change if the list changed.
+    pub fn handle_event(&mut self, event: &Event<'_>) -> Option<DeviceListChange> {
+        let devices = match event {
+            Event::PropertyChange {
+                name: "audio-device-list",
+                change: PropertyData::Node(node),
+                reply_userdata,
+            } if *reply_userdata == self.reply_userdata => decode_list(node).ok()?,
+            _ => return None,
+        };
+        if devices == self.devices {
+            return None;
+        }
+        let missing_from = |list: &[AudioDevice], other: &[AudioDevice]| {
+            list.iter()
+                .filter(|device| !other.iter().any(|d| d.name == device.name))
+                .cloned()
+                .collect()
+        };
+        let change = DeviceListChange {
+            added: missing_from(&devices, &self.devices),
+            removed: missing_from(&self.devices, &devices),
+            devices: devices.clone(),
+        };
+        self.devices = devices;
+        Some(change)
+    }
+
+    /// The current list.
+    pub fn devices(&self) -> &[AudioDevice] {
+        &self.devices
+    }
+
+    /// Switch `mpv` back to `auto` if its selected device is no longer in the
+    /// list. Returns whether it did.
+    pub fn fall_back(&self, mpv: &Mpv) -> Result<bool> {
+        let selected = mpv.audio_device()?;
+        if self.devices.iter().any(|device| device.name == selected) {
+            return Ok(false);
+        }
+        mpv.set_property("audio-device", "auto")?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,494 @@
+//! Decoded audio samples.
+//!
+//! libmpv has no audio callback. An [`AudioTap`] plays a file in a headless
+//! instance with the `pcm` audio output writing into a FIFO, and reads the
+//! samples back from it. Decoding stalls while the caller doesn't take blocks,
+//! so only about a second of audio is buffered.
+//!
+//! Where there are no FIFOs, i.e. on Windows, the output goes to a temporary
+//! file instead. Decoding is not held back there, and the file grows to the
+//! size of the whole decoded audio (about 1.4 GB per hour at 48 kHz stereo)
+//! until the tap is dropped.
+//!
+//! ```no_run
+//! # use libmpv2::audio_tap::{AudioTap, AudioTapOptions};
+//! # fn main() -> libmpv2::Result<()> {
+//! let tap = AudioTap::open("song.flac", &AudioTapOptions::default())?;
+//! for block in tap {
+//!     let block = block?;
+//!     let peak = block.samples.iter().fold(0f32, |peak, s| peak.max(s.abs()));
+//!     println!("{:.2}s: {}", block.time, peak);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::events::Event;
+use crate::{Error, Mpv, Result, mpv_error};
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Options for [`AudioTap::open`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioTapOptions {
+    /// Number of channels the audio is mixed to. Defaults to 2.
+    pub channels: u32,
+    /// Sample rate the audio is resampled to. Defaults to 48000.
+    pub sample_rate: u32,
+    /// Number of frames (samples per channel) in each block. Defaults to 4096.
+    pub block_frames: usize,
+    /// Where to start decoding, in seconds.
+    pub start: Option<f64>,
+    /// How long to wait for the file to be opened and for mpv to produce
+    /// samples. Defaults to 10 seconds.
+    pub timeout: Duration,
+    /// Additional options for the `Mpv` instance, e.g. `af` or `aid`.
+    pub options: Vec<(String, String)>,
+}
+
+impl Default for AudioTapOptions {
+    fn default() -> AudioTapOptions {
+        AudioTapOptions {
+            channels: 2,
+            sample_rate: 48000,
+            block_frames: 4096,
+            start: None,
+            timeout: Duration::from_secs(10),
+            options: Vec::new(),
+        }
+    }
+}
+
+impl AudioTapOptions {
+    pub fn channels(mut self, channels: u32) -> AudioTapOptions {
+        self.channels = channels;
+        self
+    }
+
+    pub fn sample_rate(mut self, sample_rate: u32) -> AudioTapOptions {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    pub fn block_frames(mut self, block_frames: usize) -> AudioTapOptions {
+        self.block_frames = block_frames;
+        self
+    }
+
+    pub fn start(mut self, start: f64) -> AudioTapOptions {
+        self.start = Some(start);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> AudioTapOptions {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn option<K: Into<String>, V: Into<String>>(
+        mut self,
+        name: K,
+        value: V,
+    ) -> AudioTapOptions {
+        self.options.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// Interleaved 32 bit float samples, from -1 to 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioBlock {
+    /// Position of the first sample in the file, in seconds, on the same
+    /// timeline as `time-pos`.
+    pub time: f64,
+    pub channels: u32,
+    pub sample_rate: u32,
+    pub samples: Vec<f32>,
+}
+
+impl AudioBlock {
+    /// Number of frames, i.e. samples per channel.
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+
+    /// Length of the block in seconds.
+    pub fn duration(&self) -> f64 {
+        self.frames() as f64 / self.sample_rate as f64
+    }
+
+    /// The samples of each frame.
+    pub fn frame_iter(&self) -> impl Iterator<Item = &[f32]> {
+        self.samples.chunks_exact(self.channels.max(1) as usize)
+    }
+}
+
+// Distinguishes the outputs of several taps in one process.
+static TAP_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+// How long to wait for new samples or events before checking again.
+const POLL_INTERVAL: f64 = 0.02;
+
+#[cfg(unix)]
+fn make_output(path: &Path) -> io::Result<()> {
+    use std::ffi::{CString, c_char, c_int};
+    use std::os::unix::ffi::OsStrExt;
+
+    #[cfg(any(
+        target_vendor = "apple",
+        target_os = "freebsd",
+        target_os = "dragonfly"
+    ))]
+    type Mode = u16;
+    #[cfg(not(any(
+        target_vendor = "apple",
+        target_os = "freebsd",
+        target_os = "dragonfly"
+    )))]
+    type Mode = u32;
+
+    unsafe extern "C" {
+        fn mkfifo(path: *const c_char, mode: Mode) -> c_int;
+    }
+
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    if unsafe { mkfifo(path.as_ptr(), 0o600) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// The `pcm` output creates the file.
+#[cfg(not(unix))]
+fn make_output(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+// Open the output for reading. Opening a FIFO blocks until mpv opens it for
+// writing, or until `wake_reader` is called. Returns `None` once the output is
+// gone.
+#[cfg(unix)]
+fn open_output(path: &Path, _ended: &AtomicBool) -> io::Result<Option<File>> {
+    match File::open(path) {
+        Ok(file) => Ok(Some(file)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(not(unix))]
+fn open_output(path: &Path, ended: &AtomicBool) -> io::Result<Option<File>> {
+    loop {
+        let ended = ended.load(Ordering::SeqCst);
+        match File::open(path) {
+            Ok(file) => return Ok(Some(file)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !ended => {
+                thread::sleep(Duration::from_secs_f64(POLL_INTERVAL));
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// Unblock a reader waiting in `open_output` for a writer, by opening the FIFO
+// as both and closing it again, which it reads as the end of the output.
+#[cfg(unix)]
+fn wake_reader(path: &Path) {
+    let _ = fs::OpenOptions::new().read(true).write(true).open(path);
+}
+
+#[cfg(not(unix))]
+fn wake_reader(_path: &Path) {}
+
+// Read the output in blocks of `block_bytes` and send them to the tap, until
+// the output ends after `ended` was set. If the tap is dropped, the rest is
+// read and discarded, so mpv isn't stuck writing while it is destroyed.
+fn read_output(
+    path: PathBuf,
+    block_bytes: usize,
+    ended: Arc<AtomicBool>,
+    blocks: SyncSender<io::Result<Vec<u8>>>,
+) {
+    let mut block = vec![0; block_bytes];
+    let mut filled = 0;
+    let mut discard = false;
+    let mut output = None;
+    loop {
+        let file = match &mut output {
+            Some(file) => file,
+            None => match open_output(&path, &ended) {
+                Ok(Some(file)) => output.insert(file),
+                Ok(None) => break,
+                Err(e) => {
+                    let _ = blocks.send(Err(e));
+                    return;
+                }
+            },
+        };
+        // Read after checking, so nothing written before the end is missed.
+        let ended = ended.load(Ordering::SeqCst);
+        match file.read(&mut block[filled..]) {
+            Ok(0) if ended => break,
+            // mpv closed the FIFO, e.g. to reconfigure the output. It opens
+            // it again, unless the file ends.
+            Ok(0) if cfg!(unix) => output = None,
+            // The temporary file hasn't grown yet.
+            Ok(0) => thread::sleep(Duration::from_secs_f64(POLL_INTERVAL)),
+            Ok(n) => {
+                filled += n;
+                if filled == block_bytes {
+                    filled = 0;
+                    if !discard && blocks.send(Ok(block.clone())).is_err() {
+                        discard = true;
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => {
+                let _ = blocks.send(Err(e));
+                return;
+            }
+        }
+    }
+    if filled > 0 && !discard {
+        block.truncate(filled);
+        let _ = blocks.send(Ok(block));
+    }
+}
+
+// The output file of a tap, removed when dropped.
+struct Output {
+    path: PathBuf,
+    // Set once mpv is done with the file, so the reader stops at the end of
+    // the output.
+    ended: Arc<AtomicBool>,
+}
+
+impl Drop for Output {
+    fn drop(&mut self) {
+        self.ended.store(true, Ordering::SeqCst);
+        wake_reader(&self.path);
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Decodes a file and yields its audio as [`AudioBlock`]s. See the
+/// [module docs](self).
+///
+/// Timestamps count the samples from the position playback started at, so
+/// they stay aligned with `time-pos` as long as the instance isn't seeked.
+pub struct AudioTap {
+    // Dropped before `mpv`, so the reader stops waiting for the tap and mpv
+    // can finish writing when it is destroyed.
+    blocks: Receiver<io::Result<Vec<u8>>>,
+    mpv: Mpv,
+    // Dropped after `mpv`, so the file is only removed once mpv is done with
+    // it.
+    output: Output,
+    channels: u32,
+    sample_rate: u32,
+    timeout: Duration,
+    // `time-pos` when playback started, once known.
+    start: Option<f64>,
+    frames_read: u64,
+    done: bool,
+}
+
+impl AudioTap {
+    /// Start decoding `path`.
+    ///
+    /// Will return `Err` if the instance or its output can't be created.
+    /// Errors opening the file are returned by the first call to `next`.
+    pub fn open(path: &str, options: &AudioTapOptions) -> Result<AudioTap> {
+        let output = std::env::temp_dir().join(format!(
+            "libmpv2-audio-tap-{}-{}.pcm",
+            std::process::id(),
+            TAP_COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let output_str = output
+            .to_str()
+            .ok_or(Error::Raw(mpv_error::InvalidParameter))?;
+        make_output(&output)?;
+
+        let mpv = Mpv::with_initializer(|init| {
+            for (name, value) in [
+                ("config", "no"),
+                ("load-scripts", "no"),
+                ("ytdl", "no"),
+                ("vid", "no"),
+                ("sid", "no"),
+                ("vo", "null"),
+                ("keep-open", "no"),
+                // Close the output at the end of the file, before `end-file`
+                // is reported.
+                ("gapless-audio", "no"),
+                ("idle", "no"),
+                ("ao", "pcm"),
+                ("ao-pcm-file", output_str),
+                ("ao-pcm-waveheader", "no"),
+                ("audio-format", "float"),
+            ] {
+                init.set_property(name, value)?;
+            }
+            init.set_property("audio-channels", &options.channels.to_string()[..])?;
+            init.set_property("audio-samplerate", options.sample_rate as i64)?;
+            if let Some(start) = options.start {
+                init.set_property("start", &start.to_string()[..])?;
+            }
+            for (name, value) in &options.options {
+                init.set_property(name, &value[..])?;
+            }
+            Ok(())
+        });
+        let mpv = match mpv.and_then(|mpv| mpv.command("loadfile", &[path]).map(|_| mpv)) {
+            Ok(mpv) => mpv,
+            Err(e) => {
+                let _ = fs::remove_file(&output);
+                return Err(e);
+            }
+        };
+
+        let block_frames = options.block_frames.max(1);
+        let block_bytes = block_frames * options.channels.max(1) as usize * 4;
+        // About a second of audio.
+        let capacity = options.sample_rate as usize / block_frames + 1;
+        let (sender, blocks) = mpsc::sync_channel(capacity);
+        let ended = Arc::new(AtomicBool::new(false));
+        let reader_output = output.clone();
+        let reader_ended = ended.clone();
+        thread::spawn(move || read_output(reader_output, block_bytes, reader_ended, sender));
+
+        Ok(AudioTap {
+            blocks,
+            mpv,
+            output: Output {
+                path: output,
+                ended,
+            },
+            channels: options.channels,
+            sample_rate: options.sample_rate,
+            timeout: options.timeout,
+            start: None,
+            frames_read: 0,
+            done: false,
+        })
+    }
+
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// The underlying instance, e.g. to read properties of the file.
+    pub fn mpv(&self) -> &Mpv {
+        &self.mpv
+    }
+
+    fn ended(&self) -> bool {
+        self.output.ended.load(Ordering::SeqCst)
+    }
+
+    // Handle one event, waiting up to `timeout` seconds for it. Returns
+    // whether there was one.
+    fn poll_event(&mut self, timeout: f64) -> Result<bool> {
+        match self.mpv.wait_event(timeout) {
+            Some(Ok(Event::PlaybackRestart)) if self.start.is_none() => {
+                self.start = Some(self.mpv.get_property("time-pos").unwrap_or(0.));
+            }
+            Some(Ok(Event::EndFile { error, .. })) if self.start.is_none() || error.is_some() => {
+                return Err(Error::Loadfile {
+                    error: Rc::new(error.unwrap_or(Error::Raw(mpv_error::NothingToPlay))),
+                });
+            }
+            Some(Ok(Event::EndFile { .. })) | Some(Ok(Event::Shutdown)) => {
+                self.output.ended.store(true, Ordering::SeqCst);
+            }
+            Some(Err(e)) => return Err(Error::Loadfile { error: Rc::new(e) }),
+            Some(Ok(_)) => {}
+            None => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn take_block(&mut self, bytes: &[u8]) -> AudioBlock {
+        let frames = bytes.len() / (self.channels as usize * 4);
+        let samples = bytes[..frames * self.channels as usize * 4]
+            .chunks_exact(4)
+            .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        let time = self.start.unwrap_or(0.) + self.frames_read as f64 / self.sample_rate as f64;
+        self.frames_read += frames as u64;
+        AudioBlock {
+            time,
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            samples,
+        }
+    }
+
+    fn next_block(&mut self) -> Result<Option<AudioBlock>> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            // Handle pending events first, so the start time is known before
+            // the first samples are returned.
+            while self.poll_event(0.)? {}
+            if self.start.is_none() {
+                if Instant::now() >= deadline {
+                    return Err(Error::Timeout);
+                }
+                self.poll_event(POLL_INTERVAL)?;
+                continue;
+            }
+
+            match self
+                .blocks
+                .recv_timeout(Duration::from_secs_f64(POLL_INTERVAL))
+            {
+                Ok(bytes) => {
+                    let bytes = bytes?;
+                    if bytes.len() >= self.channels as usize * 4 {
+                        return Ok(Some(self.take_block(&bytes)));
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => return Ok(None),
+                Err(RecvTimeoutError::Timeout) => {
+                    // The reader may be waiting for mpv to open the output
+                    // again, which it won't after the end.
+                    if self.ended() {
+                        wake_reader(&self.output.path);
+                    }
+                    if Instant::now() >= deadline {
+                        return Err(Error::Timeout);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for AudioTap {
+    type Item = Result<AudioBlock>;
+
+    fn next(&mut self) -> Option<Result<AudioBlock>> {
+        if self.done {
+            return None;
+        }
+        let block = self.next_block().transpose();
+        if !matches!(block, Some(Ok(_))) {
+            self.done = true;
+        }
+        block
+    }
+}
@@ -0,0 +1,153 @@
+//! The demuxer cache and buffering.
+
+use crate::events::{Event, PropertyData};
+use crate::mpv_node::MpvNode;
+use crate::{Error, Format, Mpv, Result};
+
+/// A time range in the demuxer cache that can be seeked to without reading
+/// from the network.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SeekableRange {
+    /// Start of the range in seconds.
+    pub start: f64,
+    /// End of the range in seconds.
+    pub end: f64,
+}
+
+/// The `demuxer-cache-state` property.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheState {
+    /// Cached ranges, in no particular order.
+    pub seekable_ranges: Vec<SeekableRange>,
+    /// Time of the last cached packet, in seconds. `None` if unknown.
+    pub cache_end: Option<f64>,
+    /// Seconds cached ahead of the playback position. `None` if unknown.
+    pub cache_duration: Option<f64>,
+    /// Bytes cached ahead of the playback position.
+    pub fw_bytes: i64,
+    /// Whether playback ran out of data and waits for the network.
+    pub underrun: bool,
+    /// Whether the end of the file was reached while reading ahead.
+    pub eof: bool,
+    /// Rate at which the network is read, in bytes per second. `None` if
+    /// unknown, e.g. for local files.
+    pub raw_input_rate: Option<i64>,
+}
+
+impl CacheState {
+    /// Decode `demuxer-cache-state` read as a node.
+    pub fn from_node(node: &MpvNode) -> Result<CacheState> {
+        let seekable_ranges = match node.get("seekable-ranges").and_then(MpvNode::array) {
+            Some(ranges) => ranges
+                .iter()
+                .map(|range| {
+                    let time = |key| {
+                        range.get(key).and_then(MpvNode::f64).ok_or_else(|| {
+                            Error::Conversion(format!("seekable range without {}", key))
+                        })
+                    };
+                    Ok(SeekableRange {
+                        start: time("start")?,
+                        end: time("end")?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            None => Vec::new(),
+        };
+        let flag = |key| node.get(key).and_then(MpvNode::bool).unwrap_or(false);
+        Ok(CacheState {
+            seekable_ranges,
+            cache_end: node.get("cache-end").and_then(MpvNode::f64),
+            cache_duration: node.get("cache-duration").and_then(MpvNode::f64),
+            fw_bytes: node.get("fw-bytes").and_then(MpvNode::i64).unwrap_or(0),
+            underrun: flag("underrun"),
+            eof: flag("eof"),
+            raw_input_rate: node.get("raw-input-rate").and_then(MpvNode::i64),
+        })
+    }
+
+    /// Whether `time`, in seconds, is in one of the seekable ranges.
+    pub fn is_cached(&self, time: f64) -> bool {
+        self.seekable_ranges
+            .iter()
+            .any(|range| range.start <= time && time <= range.end)
+    }
+}
+
+impl Mpv {
+    /// The state of the demuxer cache of the current file.
+    ///
+    /// Will return `Err` if no file is loaded.
+    pub fn cache_state(&self) -> Result<CacheState> {
+        CacheState::from_node(&self.get_property("demuxer-cache-state")?)
+    }
+}
+
+/// A change reported by a [`BufferingMonitor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BufferingEvent {
+    /// Playback started waiting, for the cache or for a seek.
+    Started,
+    /// The cache is filled to this percentage of what playback waits for.
+    Progress(i64),
+    /// Playback continues.
+    Ended,
+}
+
+/// Observes `paused-for-cache`, `cache-buffering-state` and `seeking`, and
+/// combines them into [`BufferingEvent`]s, e.g. to show a spinner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BufferingMonitor {
+    reply_userdata: u64,
+    paused_for_cache: bool,
+    seeking: bool,
+}
+
+impl BufferingMonitor {
+    /// Observe the properties on `mpv` with `reply_userdata` as observer id.
+    pub fn observe(mpv: &Mpv, reply_userdata: u64) -> Result<BufferingMonitor> {
+        mpv.observe_property("paused-for-cache", Format::Flag, reply_userdata)?;
+        mpv.observe_property("cache-buffering-state", Format::Int64, reply_userdata)?;
+        mpv.observe_property("seeking", Format::Flag, reply_userdata)?;
+        Ok(BufferingMonitor {
+            reply_userdata,
+            paused_for_cache: false,
+            seeking: false,
+        })
+    }
+
+    /// Whether playback is currently waiting.
+    pub fn is_buffering(&self) -> bool {
+        self.paused_for_cache || self.seeking
+    }
+
+    /// Update the state from `event`. Returns what changed, if anything.
+    pub fn handle_event(&mut self, event: &Event<'_>) -> Option<BufferingEvent> {
+        let Event::PropertyChange {
+            name,
+            change,
+            reply_userdata,
+        } = event
+        else {
+            return None;
+        };
+        if *reply_userdata != self.reply_userdata {
+            return None;
+        }
+        let was_buffering = self.is_buffering();
+        match (*name, change) {
+            ("paused-for-cache", PropertyData::Flag(flag)) => self.paused_for_cache = *flag,
+            ("seeking", PropertyData::Flag(flag)) => self.seeking = *flag,
+            // The percentage is updated while playing too, as the cache fills.
+            ("cache-buffering-state", PropertyData::Int64(percent)) if was_buffering => {
+                return Some(BufferingEvent::Progress(*percent));
+            }
+            _ => return None,
+        }
+        match (was_buffering, self.is_buffering()) {
+            (false, true) => Some(BufferingEvent::Started),
+            (true, false) => Some(BufferingEvent::Ended),
+            _ => None,
+        }
+    }
+}
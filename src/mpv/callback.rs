@@ -0,0 +1,100 @@
+use crate::{Callback, CallbackError};
+
+use std::any::Any;
+#[cfg(debug_assertions)]
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::RwLock;
+
+type Handler = Box<dyn Fn(&CallbackError) + Send + Sync>;
+
+static HANDLER: RwLock<Option<Handler>> = RwLock::new(None);
+
+pub(crate) fn set_handler(handler: Option<Handler>) {
+    match HANDLER.write() {
+        Ok(mut current) => *current = handler,
+        Err(poisoned) => *poisoned.into_inner() = handler,
+    }
+}
+
+/// Pass `message` to the callback error handler, if one is set. Never unwinds.
+pub(crate) fn report(callback: Callback, message: String) {
+    let error = CallbackError { callback, message };
+    let _ = panic::catch_unwind(|| {
+        let handler = match HANDLER.read() {
+            Ok(handler) => handler,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Some(handler) = handler.as_ref() {
+            handler(&error);
+        }
+    });
+}
+
+#[cfg(debug_assertions)]
+thread_local! {
+    // The callback the current thread is executing, if any.
+    static INSIDE: Cell<Option<Callback>> = const { Cell::new(None) };
+}
+
+// Marks the current thread as being inside `callback` until dropped.
+struct Scope {
+    #[cfg(debug_assertions)]
+    previous: Option<Callback>,
+}
+
+impl Scope {
+    #[cfg_attr(not(debug_assertions), allow(unused_variables))]
+    fn enter(callback: Callback) -> Scope {
+        Scope {
+            #[cfg(debug_assertions)]
+            previous: INSIDE.with(|inside| inside.replace(Some(callback))),
+        }
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        INSIDE.with(|inside| inside.set(self.previous));
+    }
+}
+
+/// Panic if the current thread is executing a callback invoked by mpv. Calling
+/// the client API from there is forbidden and would otherwise deadlock. Only
+/// checked in debug builds.
+#[track_caller]
+#[cfg_attr(not(debug_assertions), allow(unused_variables))]
+pub(crate) fn assert_outside(api: &str) {
+    #[cfg(debug_assertions)]
+    if let Some(callback) = INSIDE.with(Cell::get) {
+        panic!(
+            "{} called from inside the {:?} callback. Calling the mpv API from callbacks \
+             invoked by mpv is not allowed, wake up another thread instead.",
+            api, callback
+        );
+    }
+}
+
+/// Run a user callback invoked from C. If it panics, the panic is reported and
+/// `fallback` returned instead of unwinding into mpv.
+pub(crate) fn catch<R>(callback: Callback, fallback: R, fun: impl FnOnce() -> R) -> R {
+    let _scope = Scope::enter(callback);
+    match panic::catch_unwind(AssertUnwindSafe(fun)) {
+        Ok(ret) => ret,
+        Err(payload) => {
+            report(callback, panic_message(payload.as_ref()));
+            fallback
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_owned()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("callback panicked")
+    }
+}
@@ -0,0 +1,171 @@
+use crate::frame::{Frame, PixelFormat};
+use crate::render::{RenderContext, RenderParam, RenderParamApiType, mpv_render_update};
+use crate::{Mpv, Result};
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+/// Where a [`FrameCapture`] writes the frames it renders.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaptureSink {
+    /// One PNG file per frame, named `<prefix><index>.png` with the index padded
+    /// to six digits. `dir` is created if it does not exist.
+    PngSequence { dir: PathBuf, prefix: String },
+    /// A single YUV4MPEG2 stream (4:4:4, BT.601 limited range) at the given
+    /// frame rate, as `(numerator, denominator)`.
+    Y4m { path: PathBuf, fps: (u32, u32) },
+    /// Frames are only rendered, and hashed if enabled.
+    Discard,
+}
+
+/// Information about a frame written by [`FrameCapture::capture`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedFrame {
+    /// Zero based number of the frame within this capture.
+    pub index: usize,
+    /// The file the frame was written to, for [`CaptureSink::PngSequence`].
+    pub path: Option<PathBuf>,
+    /// The [`Frame::hash`] of the frame, if hashing is enabled.
+    pub hash: Option<u64>,
+}
+
+/// Renders video with the software renderer at a fixed size and writes every
+/// captured frame to a [`CaptureSink`]. No display is needed, which makes this
+/// suitable for comparing subtitle and OSD rendering against golden images.
+///
+/// The `vo` option of the `Mpv` instance must be left at its default or be set
+/// to `libmpv`. Like [`RenderContext`], a `FrameCapture` must be dropped before
+/// the `Mpv` instance it was created for.
+pub struct FrameCapture {
+    ctx: RenderContext,
+    frame: Frame,
+    sink: CaptureSink,
+    y4m: Option<BufWriter<File>>,
+    hashing: bool,
+    hashes: Vec<u64>,
+    count: usize,
+}
+
+impl FrameCapture {
+    /// Create a software render context for `mpv` that renders `width`x`height`
+    /// frames into `sink`.
+    ///
+    /// Will return `Err` if the render context can't be created, or if the sink's
+    /// directory or file can't be created.
+    pub fn new(mpv: &Mpv, width: u32, height: u32, sink: CaptureSink) -> Result<FrameCapture> {
+        let y4m = match &sink {
+            CaptureSink::PngSequence { dir, .. } => {
+                fs::create_dir_all(dir)?;
+                None
+            }
+            CaptureSink::Y4m { path, fps } => {
+                let mut file = BufWriter::new(File::create(path)?);
+                writeln!(
+                    file,
+                    "YUV4MPEG2 W{} H{} F{}:{} Ip A1:1 C444",
+                    width, height, fps.0, fps.1
+                )?;
+                Some(file)
+            }
+            CaptureSink::Discard => None,
+        };
+
+        let ctx = RenderContext::new(
+            unsafe { &mut *mpv.ctx.as_ptr() },
+            vec![RenderParam::<()>::ApiType(RenderParamApiType::Software)],
+        )?;
+
+        Ok(FrameCapture {
+            ctx,
+            frame: Frame::new(width, height, PixelFormat::Rgb0),
+            sink,
+            y4m,
+            hashing: false,
+            hashes: Vec::new(),
+            count: 0,
+        })
+    }
+
+    /// Enable or disable hashing of every captured frame.
+    pub fn hashing(mut self, enable: bool) -> FrameCapture {
+        self.hashing = enable;
+        self
+    }
+
+    /// The underlying render context, e.g. to set an update callback.
+    pub fn render_context(&mut self) -> &mut RenderContext {
+        &mut self.ctx
+    }
+
+    /// Render the current video frame and write it to the sink. If no new frame is
+    /// available, the previous one is rendered again.
+    pub fn capture(&mut self) -> Result<CapturedFrame> {
+        let frame = &mut self.frame;
+        self.ctx.render_sw(
+            frame.width as _,
+            frame.height as _,
+            frame.format.as_str(),
+            frame.stride,
+            &mut frame.data,
+        )?;
+
+        let index = self.count;
+        let path = match &self.sink {
+            CaptureSink::PngSequence { dir, prefix } => {
+                let path = dir.join(format!("{}{:06}.png", prefix, index));
+                self.frame.write_png(&path)?;
+                Some(path)
+            }
+            CaptureSink::Y4m { .. } => {
+                if let Some(file) = self.y4m.as_mut() {
+                    file.write_all(b"FRAME\n")?;
+                    file.write_all(&self.frame.to_yuv444())?;
+                }
+                None
+            }
+            CaptureSink::Discard => None,
+        };
+
+        let hash = self.hashing.then(|| self.frame.hash());
+        if let Some(hash) = hash {
+            self.hashes.push(hash);
+        }
+        self.count += 1;
+
+        Ok(CapturedFrame { index, path, hash })
+    }
+
+    /// Capture a frame only if the render context reports a new one. Call this
+    /// after the update callback fired.
+    pub fn capture_update(&mut self) -> Result<Option<CapturedFrame>> {
+        if self.ctx.update()? & mpv_render_update::Frame != 0 {
+            self.capture().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// The most recently rendered frame.
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    /// The hashes of all frames captured so far, in order.
+    pub fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+
+    /// The number of frames captured so far.
+    pub fn frame_count(&self) -> usize {
+        self.count
+    }
+
+    /// Flush the sink and return the hashes of all captured frames.
+    pub fn finish(mut self) -> Result<Vec<u64>> {
+        if let Some(file) = self.y4m.as_mut() {
+            file.flush()?;
+        }
+        Ok(std::mem::take(&mut self.hashes))
+    }
+}
@@ -0,0 +1,282 @@
+//! Chapters and editions of the current file.
+
+use crate::commands::Add;
+use crate::events::{Event, PropertyData};
+use crate::mpv_node::MpvNode;
+use crate::{Error, Format, Mpv, Result, mpv_error};
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// An entry of the `chapter-list` property.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    pub title: Option<String>,
+    /// Start of the chapter in seconds.
+    pub time: f64,
+}
+
+impl Chapter {
+    /// Decode an entry of `chapter-list` read as a node.
+    pub fn from_node(node: &MpvNode) -> Result<Chapter> {
+        Ok(Chapter {
+            title: node.get("title").and_then(MpvNode::str).map(str::to_owned),
+            time: node
+                .get("time")
+                .and_then(MpvNode::f64)
+                .ok_or_else(|| Error::Conversion(String::from("chapter without a time")))?,
+        })
+    }
+
+    /// Encode the chapter as an entry of `chapter-list`.
+    pub fn to_node(&self) -> MpvNode {
+        MpvNode::from_map([
+            ("title", MpvNode::from(self.title.clone())),
+            ("time", MpvNode::Double(self.time)),
+        ])
+    }
+}
+
+fn escape_ffmetadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Format `chapters` as an FFmpeg metadata file, as read by the `chapters-file`
+/// option. Each chapter ends where the next one starts, the last one at
+/// `duration` if given.
+pub fn to_ffmetadata(chapters: &[Chapter], duration: Option<f64>) -> String {
+    let millis = |time: f64| (time * 1000.).round() as i64;
+
+    let mut out = String::from(";FFMETADATA1\n");
+    for (i, chapter) in chapters.iter().enumerate() {
+        let end = chapters
+            .get(i + 1)
+            .map(|next| next.time)
+            .or(duration)
+            .unwrap_or(chapter.time);
+        let _ = write!(
+            out,
+            "[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\n",
+            millis(chapter.time),
+            millis(end),
+        );
+        if let Some(title) = &chapter.title {
+            let _ = writeln!(out, "title={}", escape_ffmetadata(title));
+        }
+    }
+    out
+}
+
+/// Write `chapters` to `path` as an FFmpeg metadata file. See
+/// [`to_ffmetadata`].
+pub fn write_ffmetadata<P: AsRef<Path>>(
+    path: P,
+    chapters: &[Chapter],
+    duration: Option<f64>,
+) -> Result<()> {
+    fs::write(path, to_ffmetadata(chapters, duration))?;
+    Ok(())
+}
+
+/// An entry of the `edition-list` property.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Edition {
+    /// Id of the edition, used to select it with `edition`.
+    pub id: i64,
+    pub title: Option<String>,
+    /// Whether the file marks this edition as the default one.
+    pub default: bool,
+}
+
+impl Edition {
+    /// Decode an entry of `edition-list` read as a node.
+    pub fn from_node(node: &MpvNode) -> Result<Edition> {
+        Ok(Edition {
+            id: node
+                .get("id")
+                .and_then(MpvNode::i64)
+                .ok_or_else(|| Error::Conversion(String::from("edition without an id")))?,
+            title: node.get("title").and_then(MpvNode::str).map(str::to_owned),
+            default: node.get("default").and_then(MpvNode::bool).unwrap_or(false),
+        })
+    }
+}
+
+fn decode_list<T>(node: &MpvNode, decode: fn(&MpvNode) -> Result<T>) -> Result<Vec<T>> {
+    node.array()
+        .ok_or_else(|| Error::Conversion(String::from("expected an array")))?
+        .iter()
+        .map(decode)
+        .collect()
+}
+
+// `chapter` is -1 before the first chapter, and unavailable without chapters.
+fn chapter_index(chapter: i64) -> Option<usize> {
+    usize::try_from(chapter).ok()
+}
+
+/// The chapters and editions of an `Mpv` instance, returned by
+/// [`Mpv::chapters`].
+#[derive(Clone, Copy)]
+pub struct Chapters<'a> {
+    mpv: &'a Mpv,
+}
+
+impl Mpv {
+    pub fn chapters(&self) -> Chapters<'_> {
+        Chapters { mpv: self }
+    }
+}
+
+impl Chapters<'_> {
+    /// The chapters of the current file, ordered by time.
+    pub fn list(&self) -> Result<Vec<Chapter>> {
+        decode_list(
+            &self.mpv.get_property::<MpvNode>("chapter-list")?,
+            Chapter::from_node,
+        )
+    }
+
+    /// Index of the current chapter, or `None` before the first chapter or if
+    /// the file has no chapters.
+    pub fn current(&self) -> Result<Option<usize>> {
+        match self.mpv.get_property::<i64>("chapter") {
+            Ok(chapter) => Ok(chapter_index(chapter)),
+            Err(Error::Raw(mpv_error::PropertyUnavailable)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Seek to the start of the chapter at `index`.
+    pub fn set(&self, index: usize) -> Result<()> {
+        self.mpv.set_property("chapter", index as i64)
+    }
+
+    /// Seek to the next chapter. Seeking past the last chapter ends the file.
+    pub fn next(&self) -> Result<()> {
+        self.mpv.execute(&Add::new("chapter", 1.))
+    }
+
+    /// Seek to the start of the previous chapter.
+    pub fn previous(&self) -> Result<()> {
+        self.mpv.execute(&Add::new("chapter", -1.))
+    }
+
+    /// The editions of the current file.
+    pub fn editions(&self) -> Result<Vec<Edition>> {
+        match self.mpv.get_property::<MpvNode>("edition-list") {
+            Ok(node) => decode_list(&node, Edition::from_node),
+            Err(Error::Raw(mpv_error::PropertyUnavailable)) => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Id of the edition being played.
+    pub fn current_edition(&self) -> Result<i64> {
+        self.mpv.get_property("current-edition")
+    }
+
+    /// Switch to the edition `id`. This reloads the file.
+    pub fn set_edition(&self, id: i64) -> Result<()> {
+        self.mpv.set_property("edition", id)
+    }
+
+    /// Replace the chapters of the current file. They are sorted by time
+    /// first, as mpv expects.
+    pub fn replace(&self, chapters: &[Chapter]) -> Result<()> {
+        let mut chapters = chapters.to_vec();
+        chapters.sort_by(|a, b| a.time.total_cmp(&b.time));
+        self.mpv.set_property(
+            "chapter-list",
+            MpvNode::Array(chapters.iter().map(Chapter::to_node).collect()),
+        )
+    }
+
+    /// Add chapters to those of the current file.
+    pub fn extend(&self, chapters: &[Chapter]) -> Result<()> {
+        let mut list = self.list()?;
+        list.extend_from_slice(chapters);
+        self.replace(&list)
+    }
+
+    /// Load the chapters of files played from now on from an FFmpeg metadata
+    /// file, e.g. one written by [`write_ffmetadata`], instead of the file's
+    /// own chapters.
+    pub fn set_chapters_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.mpv.set_property(
+            "chapters-file",
+            path.to_str()
+                .ok_or(Error::Raw(mpv_error::InvalidParameter))?,
+        )
+    }
+}
+
+/// A change reported by a [`ChapterObserver`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChapterChange {
+    /// `chapter-list` changed, e.g. because a new file was loaded.
+    List(Vec<Chapter>),
+    /// The current chapter changed. `None` before the first chapter.
+    Current(Option<usize>),
+    /// `edition-list` changed.
+    Editions(Vec<Edition>),
+    /// The edition being played changed.
+    CurrentEdition(i64),
+}
+
+/// Observes the chapter and edition properties and decodes their changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChapterObserver {
+    reply_userdata: u64,
+}
+
+impl ChapterObserver {
+    /// Observe `chapter-list`, `chapter`, `edition-list` and `current-edition`
+    /// on `mpv` with `reply_userdata` as observer id.
+    pub fn observe(mpv: &Mpv, reply_userdata: u64) -> Result<ChapterObserver> {
+        mpv.observe_property("chapter-list", Format::Node, reply_userdata)?;
+        mpv.observe_property("chapter", Format::Int64, reply_userdata)?;
+        mpv.observe_property("edition-list", Format::Node, reply_userdata)?;
+        mpv.observe_property("current-edition", Format::Int64, reply_userdata)?;
+        Ok(ChapterObserver { reply_userdata })
+    }
+
+    /// Decode `event` if it is a change of one of the observed properties.
+    pub fn parse(&self, event: &Event<'_>) -> Option<ChapterChange> {
+        let Event::PropertyChange {
+            name,
+            change,
+            reply_userdata,
+        } = event
+        else {
+            return None;
+        };
+        if *reply_userdata != self.reply_userdata {
+            return None;
+        }
+        match (*name, change) {
+            ("chapter-list", PropertyData::Node(node)) => decode_list(node, Chapter::from_node)
+                .ok()
+                .map(ChapterChange::List),
+            ("chapter", PropertyData::Int64(chapter)) => {
+                Some(ChapterChange::Current(chapter_index(*chapter)))
+            }
+            ("edition-list", PropertyData::Node(node)) => decode_list(node, Edition::from_node)
+                .ok()
+                .map(ChapterChange::Editions),
+            ("current-edition", PropertyData::Int64(id)) => {
+                Some(ChapterChange::CurrentEdition(*id))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Exporting a time range of a file, e.g. between the A/B loop points set in a
+//! player.
+//!
+//! ```no_run
+//! # use libmpv2::{Mpv, clip::{ClipMode, export_clip}};
+//! # fn main() -> libmpv2::Result<()> {
+//! # let mpv = Mpv::new()?;
+//! if let (Some(a), Some(b)) = mpv.ab_loop()? {
+//!     let path: String = mpv.get_property("path")?;
+//!     let summary = export_clip(&path, a, b, "clip.mkv", ClipMode::Copy)?.run(|progress| {
+//!         println!("{:.0}%", progress.percent);
+//!         true
+//!     })?;
+//!     println!("wrote {}", summary.output.display());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::encoder::{EncodeEvent, EncodeSummary, Encoder, Encoding, Progress};
+use crate::probe::probe;
+use crate::{Error, Mpv, Result, mpv_error};
+
+use std::path::{Path, PathBuf};
+
+/// Encoder settings for re-encoded clips.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ClipPreset {
+    /// H.264 and AAC, for sharing.
+    #[default]
+    H264,
+    /// VP9 and Opus, for the web.
+    Vp9,
+    /// FFV1 and FLAC, for further editing.
+    Lossless,
+}
+
+impl ClipPreset {
+    /// Configure `encoder` for this preset. The container is still chosen by
+    /// the extension of the output.
+    pub fn apply(&self, encoder: Encoder) -> Encoder {
+        match *self {
+            ClipPreset::H264 => encoder
+                .video_codec("libx264")
+                .video_option("preset", "veryfast")
+                .video_option("crf", "20")
+                .audio_codec("aac"),
+            ClipPreset::Vp9 => encoder
+                .video_codec("libvpx-vp9")
+                .video_option("crf", "32")
+                .video_option("b", "0")
+                .audio_codec("libopus"),
+            ClipPreset::Lossless => encoder.video_codec("ffv1").audio_codec("flac"),
+        }
+    }
+}
+
+/// How [`export_clip`] writes the clip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClipMode {
+    /// Copy the packets of the source with `stream-record`. Fast and lossless,
+    /// but the clip starts at the keyframe before `start`, and the output must
+    /// be a container that can hold the source's codecs.
+    Copy,
+    /// Decode and encode again, cutting exactly at `start`.
+    Reencode(ClipPreset),
+}
+
+// How much shorter than requested a clip may be, to allow for frame and audio
+// packet boundaries.
+const DURATION_TOLERANCE: f64 = 0.25;
+
+// How much longer than requested a copied clip may be, as it starts at the
+// keyframe before the range. Covers the keyframe intervals of common encoders.
+const KEYFRAME_INTERVAL: f64 = 10.;
+
+/// A running clip export, returned by [`export_clip`].
+pub struct ClipExport {
+    encoding: Encoding,
+    mode: ClipMode,
+    duration: f64,
+}
+
+/// Export `source` from `start` to `end`, in seconds, to `dest`.
+///
+/// Returns `Error::Raw(mpv_error::InvalidParameter)` if the range is empty or
+/// negative, or if `dest` is not valid UTF-8.
+///
+/// Once the export finished, the duration of `dest` is checked. `Finished`
+/// returns `Error::Verification` if the clip is shorter or longer than
+/// requested. Copied clips may be longer by up to a keyframe interval.
+pub fn export_clip<P: AsRef<Path>>(
+    source: &str,
+    start: f64,
+    end: f64,
+    dest: P,
+    mode: ClipMode,
+) -> Result<ClipExport> {
+    if !(start >= 0. && end > start) {
+        return Err(Error::Raw(mpv_error::InvalidParameter));
+    }
+    let dest = dest.as_ref();
+    let encoding = match mode {
+        ClipMode::Copy => {
+            let output = dest
+                .to_str()
+                .ok_or(Error::Raw(mpv_error::InvalidParameter))?;
+            let mpv = Mpv::with_initializer(|init| {
+                for (name, value) in [
+                    ("config", "no"),
+                    ("load-scripts", "no"),
+                    ("ytdl", "no"),
+                    ("vo", "null"),
+                    ("ao", "null"),
+                    ("keep-open", "no"),
+                    // Play as fast as the file can be read.
+                    ("untimed", "yes"),
+                    ("ao-null-untimed", "yes"),
+                    ("stream-record", output),
+                ] {
+                    init.set_property(name, value)?;
+                }
+                init.set_property("start", &start.to_string()[..])?;
+                init.set_property("end", &end.to_string()[..])?;
+                Ok(())
+            })?;
+            Encoding::start(mpv, source, PathBuf::from(dest), Some(start), Some(end))?
+        }
+        ClipMode::Reencode(preset) => preset
+            .apply(Encoder::new(source, dest))
+            .range(Some(start), Some(end))
+            .spawn()?,
+    };
+    Ok(ClipExport {
+        encoding,
+        mode,
+        duration: end - start,
+    })
+}
+
+impl ClipExport {
+    /// Wait up to `timeout` seconds for the next update, like
+    /// [`Encoding::wait_event`]. The result of `Finished` includes the
+    /// verification of the output.
+    pub fn wait_event(&mut self, timeout: f64) -> Option<EncodeEvent> {
+        match self.encoding.wait_event(timeout)? {
+            EncodeEvent::Finished(Ok(summary)) if !summary.cancelled => Some(
+                EncodeEvent::Finished(self.verify(&summary).map(|_| summary)),
+            ),
+            event => Some(event),
+        }
+    }
+
+    fn verify(&self, summary: &EncodeSummary) -> Result<()> {
+        let actual = probe(&summary.output)?.duration.ok_or_else(|| {
+            Error::Verification(format!("{} has no duration", summary.output.display()))
+        })?;
+        let max = match self.mode {
+            // The clip starts at a keyframe before the start of the range.
+            ClipMode::Copy => self.duration + KEYFRAME_INTERVAL + DURATION_TOLERANCE,
+            ClipMode::Reencode(_) => self.duration + DURATION_TOLERANCE,
+        };
+        if actual < self.duration - DURATION_TOLERANCE || actual > max {
+            return Err(Error::Verification(format!(
+                "{} is {:.3}s long, expected {:.3}s",
+                summary.output.display(),
+                actual,
+                self.duration
+            )));
+        }
+        Ok(())
+    }
+
+    /// Stop the export. The partial clip is kept and not verified.
+    pub fn cancel(&mut self) -> Result<()> {
+        self.encoding.cancel()
+    }
+
+    /// Wait until the export is done, calling `progress` on every update. The
+    /// export is cancelled if `progress` returns `false`.
+    pub fn run<F: FnMut(&Progress) -> bool>(mut self, mut progress: F) -> Result<EncodeSummary> {
+        loop {
+            match self.wait_event(1.) {
+                Some(EncodeEvent::Progress(update)) if !progress(&update) => self.cancel()?,
+                Some(EncodeEvent::Finished(result)) => return result,
+                Some(EncodeEvent::Progress(_)) | None => {}
+            }
+        }
+    }
+}
+
+impl Mpv {
+    /// The A and B points of the A/B loop, in seconds, or `None` where they
+    /// are not set.
+    pub fn ab_loop(&self) -> Result<(Option<f64>, Option<f64>)> {
+        let point = |name| -> Result<Option<f64>> {
+            match &self.get_property::<String>(name)?[..] {
+                "no" => Ok(None),
+                value => value
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| Error::Conversion(format!("invalid {} value {}", name, value))),
+            }
+        };
+        Ok((point("ab-loop-a")?, point("ab-loop-b")?))
+    }
+
+    /// Set the A and B points of the A/B loop. `None` clears a point.
+    pub fn set_ab_loop(&self, a: Option<f64>, b: Option<f64>) -> Result<()> {
+        let value =
+            |point: Option<f64>| point.map_or_else(|| String::from("no"), |p| p.to_string());
+        self.set_property("ab-loop-a", &value(a)[..])?;
+        self.set_property("ab-loop-b", &value(b)[..])
+    }
+}
@@ -0,0 +1,829 @@
+//! Typed builders for mpv's input commands.
+//!
+//! Every command is a struct implementing [`Command`], which encodes it as a
+//! node with named arguments and decodes its result. Run them with
+//! [`Mpv::execute`]:
+//!
+//! ```no_run
+//! # use libmpv2::{Mpv, commands::*};
+//! # fn main() -> libmpv2::Result<()> {
+//! let mpv = Mpv::new()?;
+//! mpv.execute(&LoadFile::new("video.mkv").mode(LoadFileMode::AppendPlay))?;
+//! mpv.execute(&Seek::absolute(42.).precision(SeekPrecision::Exact))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::filters::FilterKind;
+use crate::frame::Frame;
+use crate::mpv_node::MpvNode;
+use crate::tracks::TrackType;
+use crate::{Mpv, Result};
+
+/// A command that can be run with [`Mpv::execute`].
+pub trait Command {
+    /// The typed result of the command.
+    type Output;
+
+    /// The command as a node: a map with the command `name` and its named
+    /// arguments.
+    fn to_node(&self) -> MpvNode;
+
+    /// Decode the node returned by mpv.
+    fn decode(result: MpvNode) -> Result<Self::Output>;
+}
+
+impl Mpv {
+    /// Run a typed command and decode its result.
+    pub fn execute<C: Command + ?Sized>(&self, command: &C) -> Result<C::Output> {
+        C::decode(self.command_node(&command.to_node())?)
+    }
+}
+
+// Commands without a meaningful result.
+macro_rules! unit_output {
+    () => {
+        type Output = ();
+
+        fn decode(_result: MpvNode) -> Result<()> {
+            Ok(())
+        }
+    };
+}
+
+fn command(name: &str, args: Vec<(&str, MpvNode)>) -> MpvNode {
+    let mut map = vec![(String::from("name"), MpvNode::from(name))];
+    map.extend(args.into_iter().map(|(k, v)| (k.to_owned(), v)));
+    MpvNode::Map(map)
+}
+
+/// Where `loadfile` puts the new entry, and whether it starts playing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LoadFileMode {
+    /// Stop playback and play the new file immediately.
+    #[default]
+    Replace,
+    /// Append the file to the playlist.
+    Append,
+    /// Append the file, and start playing it if nothing is playing.
+    AppendPlay,
+    /// Insert the file after the current entry.
+    InsertNext,
+    /// Insert the file after the current entry, and start playing it if
+    /// nothing is playing.
+    InsertNextPlay,
+    /// Insert the file at the given playlist index (requires mpv 0.38).
+    InsertAt(i64),
+    /// Insert the file at the given playlist index, and start playing it if
+    /// nothing is playing (requires mpv 0.38).
+    InsertAtPlay(i64),
+}
+
+impl LoadFileMode {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            LoadFileMode::Replace => "replace",
+            LoadFileMode::Append => "append",
+            LoadFileMode::AppendPlay => "append-play",
+            LoadFileMode::InsertNext => "insert-next",
+            LoadFileMode::InsertNextPlay => "insert-next-play",
+            LoadFileMode::InsertAt(_) => "insert-at",
+            LoadFileMode::InsertAtPlay(_) => "insert-at-play",
+        }
+    }
+
+    fn index(&self) -> Option<i64> {
+        match *self {
+            LoadFileMode::InsertAt(index) | LoadFileMode::InsertAtPlay(index) => Some(index),
+            _ => None,
+        }
+    }
+}
+
+/// `loadfile`: load a file or URL into the playlist.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadFile {
+    pub url: String,
+    pub mode: LoadFileMode,
+    /// Options set while the file is playing, e.g. `("start", "30")`.
+    pub options: Vec<(String, String)>,
+}
+
+impl LoadFile {
+    pub fn new<S: Into<String>>(url: S) -> LoadFile {
+        LoadFile {
+            url: url.into(),
+            mode: LoadFileMode::default(),
+            options: Vec::new(),
+        }
+    }
+
+    pub fn mode(mut self, mode: LoadFileMode) -> LoadFile {
+        self.mode = mode;
+        self
+    }
+
+    /// Set a per-file option.
+    pub fn option<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> LoadFile {
+        self.options.push((name.into(), value.into()));
+        self
+    }
+}
+
+impl Command for LoadFile {
+    /// The playlist entry id of the new entry, if mpv reports it.
+    type Output = Option<i64>;
+
+    fn to_node(&self) -> MpvNode {
+        let mut args = vec![
+            ("url", MpvNode::from(&self.url[..])),
+            ("flags", MpvNode::from(self.mode.as_str())),
+        ];
+        if let Some(index) = self.mode.index() {
+            args.push(("index", MpvNode::Int64(index)));
+        }
+        if !self.options.is_empty() {
+            args.push((
+                "options",
+                MpvNode::from_map(self.options.iter().map(|(k, v)| (&k[..], &v[..]))),
+            ));
+        }
+        command("loadfile", args)
+    }
+
+    fn decode(result: MpvNode) -> Result<Option<i64>> {
+        Ok(result.get("playlist_entry_id").and_then(MpvNode::i64))
+    }
+}
+
+/// What the target of a [`Seek`] is relative to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SeekMode {
+    /// Seconds relative to the current position.
+    #[default]
+    Relative,
+    /// A position in seconds. Negative values are relative to the end.
+    Absolute,
+    /// A position in percent of the file.
+    AbsolutePercent,
+    /// Percent of the file relative to the current position.
+    RelativePercent,
+}
+
+impl SeekMode {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            SeekMode::Relative => "relative",
+            SeekMode::Absolute => "absolute",
+            SeekMode::AbsolutePercent => "absolute-percent",
+            SeekMode::RelativePercent => "relative-percent",
+        }
+    }
+}
+
+/// How exactly a [`Seek`] hits its target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SeekPrecision {
+    /// Use the `hr-seek` option.
+    #[default]
+    Default,
+    /// Seek to the nearest keyframe.
+    Keyframes,
+    /// Seek to the exact position, decoding from the previous keyframe.
+    Exact,
+}
+
+/// `seek`: change the playback position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Seek {
+    pub target: f64,
+    pub mode: SeekMode,
+    pub precision: SeekPrecision,
+}
+
+impl Seek {
+    pub fn new(target: f64, mode: SeekMode) -> Seek {
+        Seek {
+            target,
+            mode,
+            precision: SeekPrecision::Default,
+        }
+    }
+
+    /// Seek by `seconds` relative to the current position.
+    pub fn relative(seconds: f64) -> Seek {
+        Seek::new(seconds, SeekMode::Relative)
+    }
+
+    /// Seek to the position `seconds`.
+    pub fn absolute(seconds: f64) -> Seek {
+        Seek::new(seconds, SeekMode::Absolute)
+    }
+
+    pub fn precision(mut self, precision: SeekPrecision) -> Seek {
+        self.precision = precision;
+        self
+    }
+
+    fn flags(&self) -> String {
+        match self.precision {
+            SeekPrecision::Default => self.mode.as_str().to_owned(),
+            SeekPrecision::Keyframes => format!("{}+keyframes", self.mode.as_str()),
+            SeekPrecision::Exact => format!("{}+exact", self.mode.as_str()),
+        }
+    }
+}
+
+impl Command for Seek {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        command(
+            "seek",
+            vec![
+                ("target", MpvNode::Double(self.target)),
+                ("flags", MpvNode::from(self.flags())),
+            ],
+        )
+    }
+}
+
+/// `playlist-next`: go to the next playlist entry. With `force`, playback is
+/// stopped at the end of the playlist instead of doing nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PlaylistNext {
+    pub force: bool,
+}
+
+impl Command for PlaylistNext {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        let flags = if self.force { "force" } else { "weak" };
+        command("playlist-next", vec![("flags", MpvNode::from(flags))])
+    }
+}
+
+/// `playlist-prev`: go to the previous playlist entry. With `force`, playback is
+/// stopped at the start of the playlist instead of doing nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PlaylistPrev {
+    pub force: bool,
+}
+
+impl Command for PlaylistPrev {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        let flags = if self.force { "force" } else { "weak" };
+        command("playlist-prev", vec![("flags", MpvNode::from(flags))])
+    }
+}
+
+/// A playlist position as understood by the `playlist-*` commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlaylistIndex {
+    /// Zero based index into the playlist.
+    Index(i64),
+    /// The currently playing entry.
+    Current,
+    /// No entry, e.g. to stop playback.
+    None,
+}
+
+impl From<PlaylistIndex> for MpvNode {
+    fn from(index: PlaylistIndex) -> MpvNode {
+        match index {
+            PlaylistIndex::Index(index) => MpvNode::from(index.to_string()),
+            PlaylistIndex::Current => MpvNode::from("current"),
+            PlaylistIndex::None => MpvNode::from("none"),
+        }
+    }
+}
+
+/// `playlist-play-index`: start playing the given entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlaylistPlayIndex(pub PlaylistIndex);
+
+impl Command for PlaylistPlayIndex {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        command("playlist-play-index", vec![("index", self.0.into())])
+    }
+}
+
+/// `playlist-clear`: remove all entries except the current one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlaylistClear;
+
+impl Command for PlaylistClear {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        command("playlist-clear", vec![])
+    }
+}
+
+/// `playlist-remove`: remove an entry. Removing the current entry stops
+/// playback and starts the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlaylistRemove(pub PlaylistIndex);
+
+impl Command for PlaylistRemove {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        command("playlist-remove", vec![("index", self.0.into())])
+    }
+}
+
+/// `playlist-move`: move the entry at index `from` so it ends up before the
+/// entry that was at index `to`. A `to` equal to the length of the playlist
+/// moves it to the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlaylistMove {
+    pub from: i64,
+    pub to: i64,
+}
+
+impl Command for PlaylistMove {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        command(
+            "playlist-move",
+            vec![
+                ("index1", MpvNode::Int64(self.from)),
+                ("index2", MpvNode::Int64(self.to)),
+            ],
+        )
+    }
+}
+
+/// `playlist-shuffle`: shuffle the playlist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlaylistShuffle;
+
+impl Command for PlaylistShuffle {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        command("playlist-shuffle", vec![])
+    }
+}
+
+/// `playlist-unshuffle`: undo the last `playlist-shuffle`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlaylistUnshuffle;
+
+impl Command for PlaylistUnshuffle {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        command("playlist-unshuffle", vec![])
+    }
+}
+
+/// How an external track added with [`TrackAdd`] is selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TrackAddFlag {
+    /// Select the track immediately.
+    #[default]
+    Select,
+    /// Don't select the track, unless the automatic selection picks it.
+    Auto,
+    /// Select an already added track with the same file name instead of adding
+    /// it again.
+    Cached,
+}
+
+impl TrackAddFlag {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            TrackAddFlag::Select => "select",
+            TrackAddFlag::Auto => "auto",
+            TrackAddFlag::Cached => "cached",
+        }
+    }
+}
+
+/// `sub-add`, `audio-add` or `video-add`: load an external track.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TrackAdd {
+    pub kind: TrackType,
+    pub url: String,
+    pub flag: TrackAddFlag,
+    pub title: Option<String>,
+    pub lang: Option<String>,
+}
+
+impl TrackAdd {
+    pub fn new<S: Into<String>>(kind: TrackType, url: S) -> TrackAdd {
+        TrackAdd {
+            kind,
+            url: url.into(),
+            flag: TrackAddFlag::default(),
+            title: None,
+            lang: None,
+        }
+    }
+
+    /// `sub-add`: load an external subtitle file.
+    pub fn sub<S: Into<String>>(url: S) -> TrackAdd {
+        TrackAdd::new(TrackType::Sub, url)
+    }
+
+    /// `audio-add`: load an external audio file.
+    pub fn audio<S: Into<String>>(url: S) -> TrackAdd {
+        TrackAdd::new(TrackType::Audio, url)
+    }
+
+    /// `video-add`: load an external video or image file.
+    pub fn video<S: Into<String>>(url: S) -> TrackAdd {
+        TrackAdd::new(TrackType::Video, url)
+    }
+
+    pub fn flag(mut self, flag: TrackAddFlag) -> TrackAdd {
+        self.flag = flag;
+        self
+    }
+
+    pub fn title<S: Into<String>>(mut self, title: S) -> TrackAdd {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn lang<S: Into<String>>(mut self, lang: S) -> TrackAdd {
+        self.lang = Some(lang.into());
+        self
+    }
+}
+
+impl Command for TrackAdd {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        let mut args = vec![
+            ("url", MpvNode::from(&self.url[..])),
+            ("flags", MpvNode::from(self.flag.as_str())),
+        ];
+        if let Some(title) = &self.title {
+            args.push(("title", MpvNode::from(&title[..])));
+        }
+        if let Some(lang) = &self.lang {
+            args.push(("lang", MpvNode::from(&lang[..])));
+        }
+        command(&format!("{}-add", self.kind.as_str()), args)
+    }
+}
+
+/// `sub-remove`, `audio-remove` or `video-remove`: remove an external track,
+/// or the selected one if `id` is `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TrackRemove {
+    pub kind: TrackType,
+    pub id: Option<i64>,
+}
+
+impl Command for TrackRemove {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        let args = self.id.map(|id| ("id", MpvNode::Int64(id)));
+        command(
+            &format!("{}-remove", self.kind.as_str()),
+            args.into_iter().collect(),
+        )
+    }
+}
+
+/// `sub-reload`, `audio-reload` or `video-reload`: reload an external track,
+/// or the selected one if `id` is `None`. The reloaded track gets a new id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TrackReload {
+    pub kind: TrackType,
+    pub id: Option<i64>,
+}
+
+impl Command for TrackReload {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        let args = self.id.map(|id| ("id", MpvNode::Int64(id)));
+        command(
+            &format!("{}-reload", self.kind.as_str()),
+            args.into_iter().collect(),
+        )
+    }
+}
+
+/// What [`ChangeFilters`] does with a filter chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilterOperation {
+    /// Replace the chain.
+    Set,
+    /// Append filters, replacing those with the same label.
+    Add,
+    /// Prepend filters.
+    Pre,
+    /// Remove filters, matched by label or by name and parameters.
+    Remove,
+    /// Add filters, or remove them if they are already in the chain.
+    Toggle,
+    /// Remove all filters.
+    Clear,
+}
+
+impl FilterOperation {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            FilterOperation::Set => "set",
+            FilterOperation::Add => "add",
+            FilterOperation::Pre => "pre",
+            FilterOperation::Remove => "remove",
+            FilterOperation::Toggle => "toggle",
+            FilterOperation::Clear => "clr",
+        }
+    }
+}
+
+/// `vf` or `af`: change the video or audio filter chain. `value` is a chain in
+/// mpv's filter syntax, as formatted by [`Filter`](crate::filters::Filter).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChangeFilters {
+    pub kind: FilterKind,
+    pub operation: FilterOperation,
+    pub value: String,
+}
+
+impl Command for ChangeFilters {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        command(
+            self.kind.as_str(),
+            vec![
+                ("operation", MpvNode::from(self.operation.as_str())),
+                ("value", MpvNode::from(&self.value[..])),
+            ],
+        )
+    }
+}
+
+/// `vf-command` or `af-command`: send a command to the libavfilter filters
+/// labelled `label`, e.g. to change a parameter while playing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FilterCommand {
+    pub kind: FilterKind,
+    pub label: String,
+    pub command: String,
+    pub argument: String,
+}
+
+impl Command for FilterCommand {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        command(
+            &format!("{}-command", self.kind.as_str()),
+            vec![
+                ("label", MpvNode::from(&self.label[..])),
+                ("command", MpvNode::from(&self.command[..])),
+                ("argument", MpvNode::from(&self.argument[..])),
+            ],
+        )
+    }
+}
+
+/// What a screenshot contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ScreenshotMode {
+    /// The video with subtitles, at the video's resolution.
+    #[default]
+    Subtitles,
+    /// The video only, without subtitles or OSD.
+    Video,
+    /// The contents of the window, including OSD, scaled as displayed.
+    Window,
+}
+
+impl ScreenshotMode {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ScreenshotMode::Subtitles => "subtitles",
+            ScreenshotMode::Video => "video",
+            ScreenshotMode::Window => "window",
+        }
+    }
+}
+
+/// `screenshot`: save a screenshot, named after the `screenshot-template`
+/// option, into `screenshot-directory`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Screenshot {
+    pub mode: ScreenshotMode,
+}
+
+impl Command for Screenshot {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        command(
+            "screenshot",
+            vec![("flags", MpvNode::from(self.mode.as_str()))],
+        )
+    }
+}
+
+/// `screenshot-to-file`: save a screenshot to `path`. The image format is
+/// chosen by the file extension.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ScreenshotToFile {
+    pub path: String,
+    pub mode: ScreenshotMode,
+}
+
+impl ScreenshotToFile {
+    pub fn new<S: Into<String>>(path: S) -> ScreenshotToFile {
+        ScreenshotToFile {
+            path: path.into(),
+            mode: ScreenshotMode::default(),
+        }
+    }
+
+    pub fn mode(mut self, mode: ScreenshotMode) -> ScreenshotToFile {
+        self.mode = mode;
+        self
+    }
+}
+
+impl Command for ScreenshotToFile {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        command(
+            "screenshot-to-file",
+            vec![
+                ("filename", MpvNode::from(&self.path[..])),
+                ("flags", MpvNode::from(self.mode.as_str())),
+            ],
+        )
+    }
+}
+
+/// `screenshot-raw`: take a screenshot and return its pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ScreenshotRaw {
+    pub mode: ScreenshotMode,
+}
+
+impl Command for ScreenshotRaw {
+    type Output = Frame;
+
+    fn to_node(&self) -> MpvNode {
+        command(
+            "screenshot-raw",
+            vec![("flags", MpvNode::from(self.mode.as_str()))],
+        )
+    }
+
+    fn decode(result: MpvNode) -> Result<Frame> {
+        Frame::from_node(&result)
+    }
+}
+
+/// `set`: set a property from its string representation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Set {
+    pub property: String,
+    pub value: String,
+}
+
+impl Set {
+    pub fn new<P: Into<String>, V: Into<String>>(property: P, value: V) -> Set {
+        Set {
+            property: property.into(),
+            value: value.into(),
+        }
+    }
+}
+
+impl Command for Set {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        command(
+            "set",
+            vec![
+                ("name", MpvNode::from(&self.property[..])),
+                ("value", MpvNode::from(&self.value[..])),
+            ],
+        )
+    }
+}
+
+/// `add`: add `value` to a numeric property, clamping it to its range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Add {
+    pub property: String,
+    pub value: f64,
+}
+
+impl Add {
+    pub fn new<P: Into<String>>(property: P, value: f64) -> Add {
+        Add {
+            property: property.into(),
+            value,
+        }
+    }
+}
+
+impl Command for Add {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        command(
+            "add",
+            vec![
+                ("name", MpvNode::from(&self.property[..])),
+                ("value", MpvNode::Double(self.value)),
+            ],
+        )
+    }
+}
+
+/// `cycle`: switch a property to its next (or previous) value, e.g. the next
+/// subtitle track.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cycle {
+    pub property: String,
+    /// Cycle backwards.
+    pub down: bool,
+}
+
+impl Cycle {
+    pub fn new<P: Into<String>>(property: P) -> Cycle {
+        Cycle {
+            property: property.into(),
+            down: false,
+        }
+    }
+
+    pub fn down(mut self) -> Cycle {
+        self.down = true;
+        self
+    }
+}
+
+impl Command for Cycle {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        let direction = if self.down { "down" } else { "up" };
+        command(
+            "cycle",
+            vec![
+                ("name", MpvNode::from(&self.property[..])),
+                ("value", MpvNode::from(direction)),
+            ],
+        )
+    }
+}
+
+/// `quit`: shut down the player, optionally with an exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Quit {
+    pub code: Option<i64>,
+}
+
+impl Command for Quit {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        let args = match self.code {
+            Some(code) => vec![("code", MpvNode::Int64(code))],
+            None => vec![],
+        };
+        command("quit", args)
+    }
+}
+
+/// `stop`: stop playback and clear the playlist, unless `keep_playlist` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Stop {
+    pub keep_playlist: bool,
+}
+
+impl Command for Stop {
+    unit_output!();
+
+    fn to_node(&self) -> MpvNode {
+        let args = if self.keep_playlist {
+            vec![("flags", MpvNode::from("keep-playlist"))]
+        } else {
+            vec![]
+        };
+        command("stop", args)
+    }
+}
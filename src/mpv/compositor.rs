@@ -0,0 +1,267 @@
+use crate::frame::{Frame, PixelFormat};
+use crate::render::{RenderContext, RenderParam, RenderParamApiType, mpv_render_update};
+use crate::{Error, Mpv, Result, mpv_error};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A rectangle within the canvas of a [`Compositor`], in pixels.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    /// Split a `width`x`height` canvas into `columns` * `rows` equally sized
+    /// tiles, in row-major order. Pixels left over by the division are not
+    /// covered.
+    pub fn grid(width: u32, height: u32, columns: u32, rows: u32) -> Vec<Rect> {
+        let (tile_w, tile_h) = (width / columns.max(1), height / rows.max(1));
+        (0..rows)
+            .flat_map(|row| {
+                (0..columns).map(move |column| Rect {
+                    x: column * tile_w,
+                    y: row * tile_h,
+                    width: tile_w,
+                    height: tile_h,
+                })
+            })
+            .collect()
+    }
+}
+
+/// How the playback clocks of the tiles relate to each other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClockSync {
+    /// Every instance plays on its own.
+    Independent,
+    /// The tile at index `leader` is the master clock. On
+    /// [`sync_clock`](Compositor::sync_clock), every other tile follows its pause
+    /// state and speed, and is seeked to the leader's position if it drifted
+    /// more than `max_drift` seconds away.
+    Shared { leader: usize, max_drift: f64 },
+}
+
+struct Tile {
+    // Declared before `mpv`, the render context must be freed first.
+    ctx: RenderContext,
+    mpv: Mpv,
+    rect: Rect,
+    frame: Frame,
+    dirty: Arc<AtomicBool>,
+}
+
+type UpdateCallback = Box<dyn Fn() + Send>;
+
+/// Renders several `Mpv` instances with the software renderer into
+/// sub-rectangles of one shared canvas, e.g. for a video wall.
+///
+/// Every tile owns its `Mpv` instance and render context. A tile is only
+/// re-rendered after its update callback reported a new frame.
+pub struct Compositor {
+    canvas: Frame,
+    tiles: Vec<Tile>,
+    clock: ClockSync,
+    update_callback: Arc<Mutex<Option<UpdateCallback>>>,
+}
+
+impl Compositor {
+    /// Create a compositor with an empty (black) `width`x`height` canvas.
+    pub fn new(width: u32, height: u32) -> Compositor {
+        Compositor {
+            canvas: Frame::new(width, height, PixelFormat::Rgb0),
+            tiles: Vec::new(),
+            clock: ClockSync::Independent,
+            update_callback: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Add `mpv` as a tile that is rendered into `rect`, and return its index.
+    /// The `vo` option of `mpv` must be left at its default or be set to
+    /// `libmpv`.
+    ///
+    /// Will return `Err` if `rect` is empty or doesn't fit into the canvas, or if
+    /// the render context can't be created.
+    pub fn add(&mut self, mpv: Mpv, rect: Rect) -> Result<usize> {
+        if rect.width == 0
+            || rect.height == 0
+            || rect
+                .x
+                .checked_add(rect.width)
+                .is_none_or(|right| right > self.canvas.width)
+            || rect
+                .y
+                .checked_add(rect.height)
+                .is_none_or(|bottom| bottom > self.canvas.height)
+        {
+            return Err(Error::Raw(mpv_error::InvalidParameter));
+        }
+
+        let mut ctx = RenderContext::new(
+            unsafe { &mut *mpv.ctx.as_ptr() },
+            vec![RenderParam::<()>::ApiType(RenderParamApiType::Software)],
+        )?;
+
+        let dirty = Arc::new(AtomicBool::new(true));
+        let tile_dirty = dirty.clone();
+        let update_callback = self.update_callback.clone();
+        ctx.set_update_callback(move || {
+            tile_dirty.store(true, Ordering::Release);
+            if let Ok(callback) = update_callback.lock()
+                && let Some(callback) = callback.as_ref()
+            {
+                callback();
+            }
+        });
+
+        self.tiles.push(Tile {
+            ctx,
+            mpv,
+            rect,
+            frame: Frame::new(rect.width, rect.height, PixelFormat::Rgb0),
+            dirty,
+        });
+        Ok(self.tiles.len() - 1)
+    }
+
+    /// Set a callback that is invoked whenever any tile has a new frame. The same
+    /// restrictions as for [RenderContext::set_update_callback] apply: do not call
+    /// any mpv API from it, but wake up the thread that calls
+    /// [render](Compositor::render).
+    pub fn set_update_callback<F: Fn() + Send + 'static>(&mut self, callback: F) {
+        if let Ok(mut cb) = self.update_callback.lock() {
+            *cb = Some(Box::new(callback));
+        }
+    }
+
+    /// The number of tiles.
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// The `Mpv` instance of tile `index`.
+    pub fn mpv(&self, index: usize) -> Option<&Mpv> {
+        self.tiles.get(index).map(|tile| &tile.mpv)
+    }
+
+    /// The `Mpv` instance of tile `index`, e.g. to wait for its events.
+    pub fn mpv_mut(&mut self, index: usize) -> Option<&mut Mpv> {
+        self.tiles.get_mut(index).map(|tile| &mut tile.mpv)
+    }
+
+    /// The rectangle tile `index` is rendered into.
+    pub fn rect(&self, index: usize) -> Option<Rect> {
+        self.tiles.get(index).map(|tile| tile.rect)
+    }
+
+    /// Render every tile that has a new frame into the canvas. Returns whether
+    /// the canvas changed.
+    pub fn render(&mut self) -> Result<bool> {
+        let mut changed = false;
+        for tile in &mut self.tiles {
+            if !tile.dirty.swap(false, Ordering::AcqRel) {
+                continue;
+            }
+            if tile.ctx.update()? & mpv_render_update::Frame != 0 {
+                render_tile(tile, &mut self.canvas)?;
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Render every tile into the canvas, whether it has a new frame or not.
+    pub fn render_all(&mut self) -> Result<()> {
+        for tile in &mut self.tiles {
+            tile.dirty.store(false, Ordering::Release);
+            tile.ctx.update()?;
+            render_tile(tile, &mut self.canvas)?;
+        }
+        Ok(())
+    }
+
+    /// The composited frame.
+    pub fn canvas(&self) -> &Frame {
+        &self.canvas
+    }
+
+    /// Change how the tiles' clocks are synchronised.
+    pub fn set_clock(&mut self, clock: ClockSync) {
+        self.clock = clock;
+    }
+
+    /// With [`ClockSync::Shared`], bring all tiles in line with the leader. Call
+    /// this periodically, e.g. once per rendered canvas. Does nothing with
+    /// [`ClockSync::Independent`].
+    ///
+    /// Tiles without a file loaded are skipped.
+    pub fn sync_clock(&self) -> Result<()> {
+        let ClockSync::Shared { leader, max_drift } = self.clock else {
+            return Ok(());
+        };
+        let leader = self
+            .tiles
+            .get(leader)
+            .ok_or(Error::Raw(mpv_error::InvalidParameter))?;
+        let Ok(position) = leader.mpv.get_property::<f64>("time-pos") else {
+            return Ok(());
+        };
+        let paused: bool = leader.mpv.get_property("pause")?;
+        let speed: f64 = leader.mpv.get_property("speed")?;
+
+        for tile in &self.tiles {
+            if std::ptr::eq(tile, leader) {
+                continue;
+            }
+            let Ok(tile_position) = tile.mpv.get_property::<f64>("time-pos") else {
+                continue;
+            };
+            if tile.mpv.get_property::<bool>("pause")? != paused {
+                tile.mpv.set_property("pause", paused)?;
+            }
+            if tile.mpv.get_property::<f64>("speed")? != speed {
+                tile.mpv.set_property("speed", speed)?;
+            }
+            if (tile_position - position).abs() > max_drift {
+                tile.mpv
+                    .command("seek", &[&position.to_string(), "absolute+exact"])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Pause or unpause all tiles at once.
+    pub fn set_paused(&self, paused: bool) -> Result<()> {
+        for tile in &self.tiles {
+            tile.mpv.set_property("pause", paused)?;
+        }
+        Ok(())
+    }
+}
+
+fn render_tile(tile: &mut Tile, canvas: &mut Frame) -> Result<()> {
+    let frame = &mut tile.frame;
+    tile.ctx.render_sw(
+        frame.width as _,
+        frame.height as _,
+        frame.format.as_str(),
+        frame.stride,
+        &mut frame.data,
+    )?;
+
+    let bpp = canvas.format.bytes_per_pixel();
+    let offset = tile.rect.x as usize * bpp;
+    for y in 0..tile.rect.height {
+        let row = frame.row(y);
+        let start = (tile.rect.y + y) as usize * canvas.stride + offset;
+        canvas.data[start..start + row.len()].copy_from_slice(row);
+    }
+    Ok(())
+}
@@ -0,0 +1,414 @@
+//! Transcoding with mpv's encoding mode.
+//!
+//! Encoding options can only be set before an instance is initialized, so an
+//! [`Encoder`] collects them and creates a dedicated instance that plays the
+//! input as fast as possible into the output file:
+//!
+//! ```no_run
+//! # use libmpv2::encoder::Encoder;
+//! # fn main() -> libmpv2::Result<()> {
+//! let summary = Encoder::new("input.mkv", "output.webm")
+//!     .video_codec("libvpx-vp9")
+//!     .video_option("crf", "32")
+//!     .audio_codec("libopus")
+//!     .range(Some(60.), Some(90.))
+//!     .run(|progress| {
+//!         println!("{:.0}% at {:.1}x", progress.percent, progress.speed);
+//!         true
+//!     })?;
+//! println!("encoded {:.1}s in {:?}", summary.position, summary.elapsed);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::commands::{LoadFile, Quit, Stop};
+use crate::events::{Event, PropertyData};
+use crate::{EndFileReason, Error, Format, Mpv, Result, mpv_end_file_reason, mpv_error};
+
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Settings of an encoding job. See the [module docs](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Encoder {
+    input: String,
+    output: PathBuf,
+    video_codec: Option<String>,
+    audio_codec: Option<String>,
+    format: Option<String>,
+    video_options: Vec<(String, String)>,
+    audio_options: Vec<(String, String)>,
+    format_options: Vec<(String, String)>,
+    start: Option<f64>,
+    end: Option<f64>,
+    options: Vec<(String, String)>,
+}
+
+impl Encoder {
+    /// Encode `input`, a path or URL, to the file `output`. Without further
+    /// settings, the container is chosen by the extension of `output` and the
+    /// codecs by the container.
+    pub fn new<I: Into<String>, O: Into<PathBuf>>(input: I, output: O) -> Encoder {
+        Encoder {
+            input: input.into(),
+            output: output.into(),
+            video_codec: None,
+            audio_codec: None,
+            format: None,
+            video_options: Vec::new(),
+            audio_options: Vec::new(),
+            format_options: Vec::new(),
+            start: None,
+            end: None,
+            options: Vec::new(),
+        }
+    }
+
+    /// `ovc`: the FFmpeg video encoder, e.g. `libx264`.
+    pub fn video_codec<S: Into<String>>(mut self, codec: S) -> Encoder {
+        self.video_codec = Some(codec.into());
+        self
+    }
+
+    /// `oac`: the FFmpeg audio encoder, e.g. `aac`.
+    pub fn audio_codec<S: Into<String>>(mut self, codec: S) -> Encoder {
+        self.audio_codec = Some(codec.into());
+        self
+    }
+
+    /// `of`: the FFmpeg container format, e.g. `matroska`.
+    pub fn format<S: Into<String>>(mut self, format: S) -> Encoder {
+        self.format = Some(format.into());
+        self
+    }
+
+    /// Add an option of the video encoder (`ovcopts`), e.g. `crf`.
+    pub fn video_option<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Encoder {
+        self.video_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Add an option of the audio encoder (`oacopts`), e.g. `b`.
+    pub fn audio_option<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Encoder {
+        self.audio_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Add an option of the muxer (`ofopts`), e.g. `movflags`.
+    pub fn format_option<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Encoder {
+        self.format_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Only encode the input from `start` to `end`, in seconds. `None` means
+    /// the start or end of the file.
+    pub fn range(mut self, start: Option<f64>, end: Option<f64>) -> Encoder {
+        self.start = start;
+        self.end = end;
+        self
+    }
+
+    /// Set any other option on the instance, e.g. `vf` or `aid`.
+    pub fn option<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Encoder {
+        self.options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Start encoding in a new instance.
+    ///
+    /// Will return `Err` if the instance can't be created or rejects one of the
+    /// options, e.g. an unknown encoder.
+    pub fn spawn(self) -> Result<Encoding> {
+        let output = self
+            .output
+            .to_str()
+            .ok_or(Error::Raw(mpv_error::InvalidParameter))?;
+
+        let mpv = Mpv::with_initializer(|init| {
+            for (name, value) in [
+                ("config", "no"),
+                ("load-scripts", "no"),
+                ("ytdl", "no"),
+                ("terminal", "no"),
+                ("keep-open", "no"),
+                ("o", output),
+            ] {
+                init.set_property(name, value)?;
+            }
+            for (name, value) in [
+                ("ovc", &self.video_codec),
+                ("oac", &self.audio_codec),
+                ("of", &self.format),
+            ] {
+                if let Some(value) = value {
+                    init.set_property(name, &value[..])?;
+                }
+            }
+            for (name, options) in [
+                ("ovcopts", &self.video_options),
+                ("oacopts", &self.audio_options),
+                ("ofopts", &self.format_options),
+            ] {
+                if !options.is_empty() {
+                    init.set_property(name, &key_value_list(options)[..])?;
+                }
+            }
+            if let Some(start) = self.start {
+                init.set_property("start", &start.to_string()[..])?;
+            }
+            if let Some(end) = self.end {
+                init.set_property("end", &end.to_string()[..])?;
+            }
+            for (name, value) in &self.options {
+                init.set_property(name, &value[..])?;
+            }
+            Ok(())
+        })?;
+        Encoding::start(mpv, &self.input, self.output, self.start, self.end)
+    }
+
+    /// Encode and wait until it's done, calling `progress` on every update.
+    /// Encoding is cancelled if `progress` returns `false`.
+    pub fn run<F: FnMut(&Progress) -> bool>(self, mut progress: F) -> Result<EncodeSummary> {
+        let mut encoding = self.spawn()?;
+        loop {
+            match encoding.wait_event(1.) {
+                Some(EncodeEvent::Progress(update)) if !progress(&update) => encoding.cancel()?,
+                Some(EncodeEvent::Finished(result)) => return result,
+                Some(EncodeEvent::Progress(_)) | None => {}
+            }
+        }
+    }
+}
+
+// Format options as an mpv key/value list, quoting values with the `%len%`
+// syntax where they contain separators.
+fn key_value_list(options: &[(String, String)]) -> String {
+    options
+        .iter()
+        .map(|(name, value)| {
+            if value.contains([',', '=', '%', '"', '\'', '[', ']']) {
+                format!("{}=%{}%{}", name, value.len(), value)
+            } else {
+                format!("{}={}", name, value)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The state of a running [`Encoding`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    /// Position in the input in seconds.
+    pub position: f64,
+    /// How much of the range has been encoded, from 0 to 100. 0 while the
+    /// duration of the input is unknown.
+    pub percent: f64,
+    /// Seconds of input encoded per second of wall time.
+    pub speed: f64,
+    /// Video frames encoded per second of wall time, if the input has video.
+    pub fps: Option<f64>,
+    /// Estimated time until encoding is done.
+    pub eta: Option<Duration>,
+}
+
+/// A finished encoding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodeSummary {
+    pub output: PathBuf,
+    /// Position in the input where encoding stopped, in seconds.
+    pub position: f64,
+    /// Wall time the encoding took.
+    pub elapsed: Duration,
+    /// Whether the encoding was cancelled. The output is still finalized, but
+    /// only contains the input up to `position`.
+    pub cancelled: bool,
+}
+
+/// An update of an [`Encoding`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum EncodeEvent {
+    Progress(Progress),
+    /// Encoding ended and the output file was closed. Returns
+    /// `Error::Loadfile` if the input could not be played.
+    Finished(Result<EncodeSummary>),
+}
+
+/// A running encoding job, returned by [`Encoder::spawn`].
+pub struct Encoding {
+    // `None` once finished.
+    mpv: Option<Mpv>,
+    output: PathBuf,
+    start: f64,
+    end: Option<f64>,
+    started: Instant,
+    position: f64,
+    frames: Option<i64>,
+    // The frame number and time of the first progress update, as the start of
+    // the frame rate measurement.
+    first_frame: Option<(i64, Instant)>,
+    cancelled: bool,
+}
+
+impl Encoding {
+    // Play `input` on `mpv`, an instance writing to `output`, and report its
+    // progress through the range.
+    pub(crate) fn start(
+        mpv: Mpv,
+        input: &str,
+        output: PathBuf,
+        start: Option<f64>,
+        end: Option<f64>,
+    ) -> Result<Encoding> {
+        mpv.observe_property("time-pos", Format::Double, 0)?;
+        mpv.observe_property("estimated-frame-number", Format::Int64, 0)?;
+        mpv.execute(&LoadFile::new(input))?;
+
+        Ok(Encoding {
+            mpv: Some(mpv),
+            output,
+            start: start.unwrap_or(0.),
+            end,
+            started: Instant::now(),
+            position: start.unwrap_or(0.),
+            frames: None,
+            first_frame: None,
+            cancelled: false,
+        })
+    }
+
+    /// Wait up to `timeout` seconds for the next update. Returns `None` on
+    /// timeout, and after [`EncodeEvent::Finished`] was returned.
+    pub fn wait_event(&mut self, timeout: f64) -> Option<EncodeEvent> {
+        let mpv = self.mpv.as_mut()?;
+        let deadline = Instant::now() + Duration::from_secs_f64(timeout.max(0.));
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let ended = match mpv.wait_event(remaining.as_secs_f64())? {
+                Ok(Event::PropertyChange {
+                    name: "time-pos",
+                    change: PropertyData::Double(position),
+                    ..
+                }) => {
+                    self.position = position;
+                    return Some(EncodeEvent::Progress(self.progress()));
+                }
+                Ok(Event::PropertyChange {
+                    name: "estimated-frame-number",
+                    change: PropertyData::Int64(frames),
+                    ..
+                }) => {
+                    self.frames = Some(frames);
+                    self.first_frame.get_or_insert((frames, Instant::now()));
+                    None
+                }
+                Ok(Event::FileLoaded) => {
+                    if self.end.is_none() {
+                        self.end = mpv.get_property("duration").ok();
+                    }
+                    None
+                }
+                Ok(Event::EndFile { error: Some(e), .. }) => Some(Err(e)),
+                Ok(Event::EndFile { reason, .. }) => Some(Ok(reason)),
+                Ok(Event::Shutdown) => Some(Ok(mpv_end_file_reason::Quit)),
+                Err(e) => Some(Err(e)),
+                Ok(_) => None,
+            };
+            if let Some(ended) = ended {
+                let result = ended
+                    .map_err(|e| Error::Loadfile { error: Rc::new(e) })
+                    .and_then(|reason| self.summary(reason));
+                self.finish();
+                return Some(EncodeEvent::Finished(result));
+            }
+        }
+    }
+
+    fn progress(&self) -> Progress {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let done = self.position - self.start;
+        let speed = if elapsed > 0. { done / elapsed } else { 0. };
+        let total = self.end.map(|end| end - self.start).filter(|&t| t > 0.);
+        let fps = self
+            .first_frame
+            .zip(self.frames)
+            .and_then(|((first, at), now)| {
+                let elapsed = at.elapsed().as_secs_f64();
+                (elapsed > 0.).then(|| (now - first) as f64 / elapsed)
+            });
+        Progress {
+            position: self.position,
+            percent: total.map_or(0., |total| (done / total * 100.).clamp(0., 100.)),
+            speed,
+            fps,
+            eta: total
+                .filter(|_| speed > 0.)
+                .map(|total| Duration::from_secs_f64(((total - done) / speed).max(0.))),
+        }
+    }
+
+    fn summary(&self, reason: EndFileReason) -> Result<EncodeSummary> {
+        if reason == mpv_end_file_reason::Error {
+            return Err(Error::Loadfile {
+                error: Rc::new(Error::Raw(mpv_error::LoadingFailed)),
+            });
+        }
+        Ok(EncodeSummary {
+            output: self.output.clone(),
+            position: self.position,
+            elapsed: self.started.elapsed(),
+            cancelled: self.cancelled,
+        })
+    }
+
+    // The output is finalized when the player shuts down.
+    fn finish(&mut self) {
+        if let Some(mut mpv) = self.mpv.take()
+            && mpv.execute(&Quit::default()).is_ok()
+        {
+            let deadline = Instant::now() + FINISH_TIMEOUT;
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero()
+                    || matches!(
+                        mpv.wait_event(remaining.as_secs_f64()),
+                        Some(Ok(Event::Shutdown))
+                    )
+                {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Stop encoding. The output is finalized with what was encoded so far,
+    /// and [`EncodeEvent::Finished`] follows with `cancelled` set.
+    pub fn cancel(&mut self) -> Result<()> {
+        let Some(mpv) = &self.mpv else {
+            return Ok(());
+        };
+        self.cancelled = true;
+        mpv.execute(&Stop::default())
+    }
+
+    /// Whether [`EncodeEvent::Finished`] was returned.
+    pub fn is_finished(&self) -> bool {
+        self.mpv.is_none()
+    }
+
+    /// The output file.
+    pub fn output(&self) -> &Path {
+        &self.output
+    }
+}
+
+// How long to wait for the output to be finalized when encoding ends.
+const FINISH_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl Drop for Encoding {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
@@ -1,4 +1,4 @@
-use std::{error, ffi::NulError, fmt, os::raw as ctype, rc::Rc, str::Utf8Error};
+use std::{error, ffi::NulError, fmt, io, os::raw as ctype, rc::Rc, str::Utf8Error};
 
 #[allow(missing_docs)]
 pub type Result<T> = ::std::result::Result<T, Error>;
@@ -15,6 +15,14 @@ pub enum Error {
     InvalidUtf8,
     Null,
     Raw(crate::MpvError),
+    /// Reading or writing a file failed.
+    Io(io::ErrorKind),
+    /// A value returned by mpv didn't have the expected structure.
+    Conversion(String),
+    /// Waiting for mpv took longer than allowed.
+    Timeout,
+    /// A file written by mpv didn't match what was requested.
+    Verification(String),
 }
 
 impl fmt::Display for Error {
@@ -34,6 +42,12 @@ impl From<Utf8Error> for Error {
         Error::InvalidUtf8
     }
 }
+impl From<io::Error> for Error {
+    fn from(other: io::Error) -> Error {
+        Error::Io(other.kind())
+    }
+}
+
 impl From<crate::MpvError> for Error {
     fn from(other: crate::MpvError) -> Error {
         Error::Raw(other)
@@ -41,3 +55,52 @@ impl From<crate::MpvError> for Error {
 }
 
 impl error::Error for Error {}
+
+/// A user supplied callback that is invoked by mpv.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Callback {
+    /// The callback set with `Mpv::set_wakeup_callback`.
+    Wakeup,
+    /// The callback set with `RenderContext::set_update_callback`.
+    RenderUpdate,
+    /// The `get_proc_address` function of `OpenGLInitParams`.
+    GetProcAddress,
+    /// One of the stream functions of a `Protocol`.
+    Protocol,
+}
+
+/// A failure inside a user callback, such as a panic, that could not be
+/// propagated to the caller because the callback was invoked by mpv.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CallbackError {
+    pub callback: Callback,
+    pub message: String,
+}
+
+impl fmt::Display for CallbackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        write!(f, "{:?} callback failed: {}", self.callback, self.message)
+    }
+}
+
+impl error::Error for CallbackError {}
+
+/// Set the handler that receives failures of callbacks invoked by mpv.
+///
+/// Panics in callbacks are caught before they reach mpv, since unwinding
+/// across the FFI boundary would abort the process. The callback then returns
+/// a neutral value (e.g. a `NULL` function pointer, or a generic error for
+/// stream callbacks) and the failure is passed to this handler instead. By
+/// default failures are dropped; the panic message is still printed by the
+/// panic hook.
+///
+/// The handler may be called from any thread, including mpv's internal
+/// threads, and the same restrictions as for the wakeup callback apply.
+pub fn set_callback_error_handler<F: Fn(&CallbackError) + Send + Sync + 'static>(handler: F) {
+    crate::mpv::callback::set_handler(Some(Box::new(handler)));
+}
+
+/// Remove the handler set with [set_callback_error_handler].
+pub fn clear_callback_error_handler() {
+    crate::mpv::callback::set_handler(None);
+}
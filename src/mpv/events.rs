@@ -1,4 +1,8 @@
-use crate::{mpv::mpv_err, *};
+use crate::{
+    mpv::{callback, mpv_err},
+    mpv_node::MpvNode,
+    *,
+};
 
 use std::ffi::{CString, c_void};
 use std::os::raw as ctype;
@@ -36,6 +40,7 @@ pub enum PropertyData<'a> {
     Flag(bool),
     Int64(i64),
     Double(ctype::c_double),
+    Node(MpvNode),
 }
 
 impl<'a> PropertyData<'a> {
@@ -56,6 +61,9 @@ impl<'a> PropertyData<'a> {
                 }
                 mpv_format::Double => Ok(PropertyData::Double(*(ptr as *mut f64))),
                 mpv_format::Int64 => Ok(PropertyData::Int64(*(ptr as *mut i64))),
+                mpv_format::Node => Ok(PropertyData::Node(MpvNode::from_raw(
+                    &*(ptr as *mut libmpv2_sys::mpv_node),
+                )?)),
                 mpv_format::None => unreachable!(),
                 _ => unimplemented!(),
             }
@@ -85,9 +93,16 @@ pub enum Event<'a> {
     /// Received when using command_async
     CommandReply(u64),
     /// Event received when a new file is playing
-    StartFile,
+    StartFile {
+        playlist_entry_id: i64,
+    },
     /// Event received when the file being played currently has stopped, for an error or not
-    EndFile(EndFileReason),
+    EndFile {
+        reason: EndFileReason,
+        playlist_entry_id: i64,
+        /// Why the file failed to load or play, if it did.
+        error: Option<Error>,
+    },
     /// Event received when a file has been *loaded*, but has not been started
     FileLoaded,
     ClientMessage(Vec<&'a str>),
@@ -104,23 +119,34 @@ pub enum Event<'a> {
     },
     /// Received when the Event Queue is full
     QueueOverflow,
+    /// A hook registered with [hook_add](Mpv::hook_add) was invoked. The
+    /// player waits until [hook_continue](Mpv::hook_continue) is called with
+    /// `id`.
+    Hook {
+        name: &'a str,
+        id: u64,
+        reply_userdata: u64,
+    },
     /// A deprecated event
     Deprecated(libmpv2_sys::mpv_event),
 }
 
 unsafe extern "C" fn wu_wrapper<F: Fn() + Send + 'static>(ctx: *mut c_void) {
     if ctx.is_null() {
-        panic!("ctx for wakeup wrapper is NULL");
+        callback::report(
+            Callback::Wakeup,
+            String::from("ctx for wakeup wrapper is NULL"),
+        );
+        return;
     }
 
-    unsafe {
-        (*(ctx as *mut F))();
-    }
+    callback::catch(Callback::Wakeup, (), || unsafe { (*(ctx as *mut F))() });
 }
 
 impl Mpv {
     /// Enable an event.
     pub fn enable_event(&self, ev: events::EventId) -> Result<()> {
+        callback::assert_outside("Mpv::enable_event");
         mpv_err((), unsafe {
             libmpv2_sys::mpv_request_event(self.ctx.as_ptr(), ev, 1)
         })
@@ -136,6 +162,7 @@ impl Mpv {
 
     /// Disable an event.
     pub fn disable_event(&self, ev: events::EventId) -> Result<()> {
+        callback::assert_outside("Mpv::disable_event");
         mpv_err((), unsafe {
             libmpv2_sys::mpv_request_event(self.ctx.as_ptr(), ev, 0)
         })
@@ -158,6 +185,7 @@ impl Mpv {
     /// Observe `name` property for changes. `id` can be used to unobserve this (or many) properties
     /// again.
     pub fn observe_property(&self, name: &str, format: Format, id: u64) -> Result<()> {
+        callback::assert_outside("Mpv::observe_property");
         let name = CString::new(name)?;
         mpv_err((), unsafe {
             libmpv2_sys::mpv_observe_property(
@@ -171,11 +199,34 @@ impl Mpv {
 
     /// Unobserve any property associated with `id`.
     pub fn unobserve_property(&self, id: u64) -> Result<()> {
+        callback::assert_outside("Mpv::unobserve_property");
         mpv_err((), unsafe {
             libmpv2_sys::mpv_unobserve_property(self.ctx.as_ptr(), id)
         })
     }
 
+    /// Register a hook handler for the hook `name`, e.g. `on_load` or
+    /// `on_preloaded`. When the hook runs, an `Event::Hook` with
+    /// `reply_userdata` is sent, and the player waits until it is continued
+    /// with [hook_continue](#method.hook_continue). Handlers with a higher
+    /// `priority` run later.
+    pub fn hook_add(&self, name: &str, reply_userdata: u64, priority: i32) -> Result<()> {
+        callback::assert_outside("Mpv::hook_add");
+        let name = CString::new(name)?;
+        mpv_err((), unsafe {
+            libmpv2_sys::mpv_hook_add(self.ctx.as_ptr(), reply_userdata, name.as_ptr(), priority)
+        })
+    }
+
+    /// Continue the hook with the `id` of an `Event::Hook`. Must be called
+    /// exactly once for every hook event.
+    pub fn hook_continue(&self, id: u64) -> Result<()> {
+        callback::assert_outside("Mpv::hook_continue");
+        mpv_err((), unsafe {
+            libmpv2_sys::mpv_hook_continue(self.ctx.as_ptr(), id)
+        })
+    }
+
     /// Wait for `timeout` seconds for an `Event`. Passing `0` as `timeout` will poll.
     /// For more information, as always, see the mpv-sys docs of `mpv_wait_event`.
     ///
@@ -185,6 +236,7 @@ impl Mpv {
     /// `MPV_EVENT_GET_PROPERTY_REPLY`, `MPV_EVENT_SET_PROPERTY_REPLY`, `MPV_EVENT_COMMAND_REPLY`,
     /// or `MPV_EVENT_PROPERTY_CHANGE` event failed, or if `MPV_EVENT_END_FILE` reported an error.
     pub fn wait_event(&mut self, timeout: f64) -> Option<Result<Event<'_>>> {
+        callback::assert_outside("Mpv::wait_event");
         let event = unsafe { *libmpv2_sys::mpv_wait_event(self.ctx.as_ptr(), timeout) };
         if event.event_id != mpv_event_id::None {
             if let Err(e) = mpv_err((), event.error) {
@@ -232,15 +284,20 @@ impl Mpv {
                 Event::CommandReply(event.reply_userdata),
                 event.error,
             )),
-            mpv_event_id::StartFile => Some(Ok(Event::StartFile)),
+            mpv_event_id::StartFile => {
+                let start_file = unsafe { *(event.data as *mut libmpv2_sys::mpv_event_start_file) };
+                Some(Ok(Event::StartFile {
+                    playlist_entry_id: start_file.playlist_entry_id,
+                }))
+            }
             mpv_event_id::EndFile => {
                 let end_file = unsafe { *(event.data as *mut libmpv2_sys::mpv_event_end_file) };
 
-                if let Err(e) = mpv_err((), end_file.error) {
-                    Some(Err(e))
-                } else {
-                    Some(Ok(Event::EndFile(end_file.reason as _)))
-                }
+                Some(Ok(Event::EndFile {
+                    reason: end_file.reason as _,
+                    playlist_entry_id: end_file.playlist_entry_id,
+                    error: mpv_err((), end_file.error).err(),
+                }))
             }
             mpv_event_id::FileLoaded => Some(Ok(Event::FileLoaded)),
             mpv_event_id::ClientMessage => {
@@ -284,6 +341,17 @@ impl Mpv {
                 }
             }
             mpv_event_id::QueueOverflow => Some(Ok(Event::QueueOverflow)),
+            mpv_event_id::Hook => {
+                let hook = unsafe { *(event.data as *mut libmpv2_sys::mpv_event_hook) };
+
+                Some(
+                    unsafe { mpv_cstr_to_str!(hook.name) }.map(|name| Event::Hook {
+                        name,
+                        id: hook.id,
+                        reply_userdata: event.reply_userdata,
+                    }),
+                )
+            }
             _ => Some(Ok(Event::Deprecated(event))),
         }
     }
@@ -298,6 +366,8 @@ impl Mpv {
     /// through any other means than a normal return is forbidden
     /// (no throwing exceptions, no `longjmp()` calls). You must not change any
     /// local thread state (such as the C floating point environment).
+    /// Panics are caught before they reach mpv and passed to the handler set
+    /// with [set_callback_error_handler](crate::set_callback_error_handler).
     ///
     /// You are not allowed to call any client API functions inside of the
     /// callback. In particular, you should not do any processing in the
@@ -308,6 +378,8 @@ impl Mpv {
     /// other things. Keep in mind that it’s also possible that the callback is
     /// called from a thread while a mpv API function is called
     /// (i.e. it can be reentrant).
+    /// In debug builds, calling any `Mpv` or `RenderContext` method from the
+    /// callback panics instead of deadlocking.
     ///
     /// In general, the client API expects you to call
     /// [wait_event](#method.wait_event) to receive notifications, and the
@@ -323,6 +395,7 @@ impl Mpv {
     ///
     /// Only one wakeup callback can be set.
     pub fn set_wakeup_callback<F: Fn() + Send + 'static>(&mut self, callback: F) {
+        callback::assert_outside("Mpv::set_wakeup_callback");
         if let Some(wakeup_callback_cleanup) = self.wakeup_callback_cleanup.take() {
             wakeup_callback_cleanup();
         }
@@ -0,0 +1,237 @@
+//! Building and changing audio and video filter chains.
+//!
+//! [`Filter`] formats filters in mpv's syntax, quoting parameter values as
+//! needed, so chains don't have to be built by string concatenation:
+//!
+//! ```no_run
+//! # use libmpv2::{Mpv, filters::{Filter, FilterKind}};
+//! # fn main() -> libmpv2::Result<()> {
+//! let mpv = Mpv::new()?;
+//! let eq = Filter::new("equalizer")
+//!     .label("eq")
+//!     .param("f", "1000")
+//!     .param("width_type", "o")
+//!     .param("g", "0");
+//! mpv.filters(FilterKind::Audio).add(&eq)?;
+//! // Later, while playing:
+//! mpv.filters(FilterKind::Audio).command("eq", "g", "4")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::commands::{ChangeFilters, FilterCommand, FilterOperation};
+use crate::mpv_node::MpvNode;
+use crate::{Error, Mpv, Result};
+
+use std::fmt;
+
+/// Whether a filter chain processes video or audio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilterKind {
+    Video,
+    Audio,
+}
+
+impl FilterKind {
+    /// The name of the chain's property and command, `vf` or `af`.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            FilterKind::Video => "vf",
+            FilterKind::Audio => "af",
+        }
+    }
+}
+
+/// A filter of a chain, in the order mpv formats it:
+/// `@label:!name=key=value:key=value`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Filter {
+    /// Name of an mpv filter, or of a libavfilter filter, e.g. `scale`.
+    pub name: String,
+    /// Label used to refer to the filter in later changes and commands.
+    pub label: Option<String>,
+    /// Disabled filters stay in the chain, but are skipped.
+    pub enabled: bool,
+    /// Parameters, in order.
+    pub params: Vec<(String, String)>,
+}
+
+impl Filter {
+    pub fn new<S: Into<String>>(name: S) -> Filter {
+        Filter {
+            name: name.into(),
+            label: None,
+            enabled: true,
+            params: Vec::new(),
+        }
+    }
+
+    /// Set the label, without the `@`.
+    pub fn label<S: Into<String>>(mut self, label: S) -> Filter {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn param<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Filter {
+        self.params.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Filter {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Decode an entry of `vf` or `af` read as a node.
+    pub fn from_node(node: &MpvNode) -> Result<Filter> {
+        let params = match node.get("params").and_then(MpvNode::map) {
+            Some(params) => params
+                .iter()
+                .map(|(key, value)| {
+                    let value = value.str().ok_or_else(|| {
+                        Error::Conversion(format!("filter parameter `{}` is not a string", key))
+                    })?;
+                    Ok((key.clone(), value.to_owned()))
+                })
+                .collect::<Result<_>>()?,
+            None => Vec::new(),
+        };
+        Ok(Filter {
+            name: node
+                .get("name")
+                .and_then(MpvNode::str)
+                .ok_or_else(|| Error::Conversion(String::from("filter without a name")))?
+                .to_owned(),
+            label: node.get("label").and_then(MpvNode::str).map(str::to_owned),
+            enabled: node.get("enabled").and_then(MpvNode::bool).unwrap_or(true),
+            params,
+        })
+    }
+}
+
+// Values with characters that separate filters or parameters are quoted as
+// `%length%value`, with the length in bytes.
+fn needs_quoting(value: &str) -> bool {
+    value.is_empty()
+        || value.chars().any(|c| {
+            matches!(c, ',' | ':' | '=' | '%' | '[' | ']' | '"' | '\'' | '\\') || c.is_whitespace()
+        })
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(label) = &self.label {
+            write!(f, "@{}:", label)?;
+        }
+        if !self.enabled {
+            f.write_str("!")?;
+        }
+        f.write_str(&self.name)?;
+        for (i, (key, value)) in self.params.iter().enumerate() {
+            let separator = if i == 0 { '=' } else { ':' };
+            if needs_quoting(value) {
+                write!(f, "{}{}=%{}%{}", separator, key, value.len(), value)?;
+            } else {
+                write!(f, "{}{}={}", separator, key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Format `filters` as a chain in mpv's syntax.
+pub fn format_chain(filters: &[Filter]) -> String {
+    filters
+        .iter()
+        .map(Filter::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The video or audio filter chain of an `Mpv` instance, returned by
+/// [`Mpv::filters`].
+#[derive(Clone, Copy)]
+pub struct Filters<'a> {
+    mpv: &'a Mpv,
+    kind: FilterKind,
+}
+
+impl Mpv {
+    pub fn filters(&self, kind: FilterKind) -> Filters<'_> {
+        Filters { mpv: self, kind }
+    }
+}
+
+impl Filters<'_> {
+    fn change(&self, operation: FilterOperation, value: String) -> Result<()> {
+        self.mpv.execute(&ChangeFilters {
+            kind: self.kind,
+            operation,
+            value,
+        })
+    }
+
+    /// The filters of the chain, in order.
+    pub fn list(&self) -> Result<Vec<Filter>> {
+        self.mpv
+            .get_property::<MpvNode>(self.kind.as_str())?
+            .array()
+            .ok_or_else(|| Error::Conversion(String::from("filter chain is not an array")))?
+            .iter()
+            .map(Filter::from_node)
+            .collect()
+    }
+
+    /// The filter labelled `label`.
+    pub fn get(&self, label: &str) -> Result<Option<Filter>> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .find(|filter| filter.label.as_deref() == Some(label)))
+    }
+
+    /// Replace the chain.
+    pub fn set(&self, filters: &[Filter]) -> Result<()> {
+        self.change(FilterOperation::Set, format_chain(filters))
+    }
+
+    /// Append `filter`, replacing a filter with the same label.
+    pub fn add(&self, filter: &Filter) -> Result<()> {
+        self.change(FilterOperation::Add, filter.to_string())
+    }
+
+    /// Insert `filter` at the start of the chain.
+    pub fn prepend(&self, filter: &Filter) -> Result<()> {
+        self.change(FilterOperation::Pre, filter.to_string())
+    }
+
+    /// Remove the filter labelled `label`.
+    pub fn remove(&self, label: &str) -> Result<()> {
+        self.change(FilterOperation::Remove, format!("@{}", label))
+    }
+
+    /// Enable the filter labelled `label` if it is disabled, and the other way
+    /// around.
+    pub fn toggle(&self, label: &str) -> Result<()> {
+        self.change(FilterOperation::Toggle, format!("@{}", label))
+    }
+
+    /// Remove all filters.
+    pub fn clear(&self) -> Result<()> {
+        self.change(FilterOperation::Clear, String::new())
+    }
+
+    /// Send `command` with `argument` to the libavfilter filter labelled
+    /// `label`, e.g. to change the gain of an `equalizer` while playing.
+    ///
+    /// Will return `Err` if nothing is playing or the filter doesn't support
+    /// the command.
+    pub fn command(&self, label: &str, command: &str, argument: &str) -> Result<()> {
+        self.mpv.execute(&FilterCommand {
+            kind: self.kind,
+            label: label.to_owned(),
+            command: command.to_owned(),
+            argument: argument.to_owned(),
+        })
+    }
+}
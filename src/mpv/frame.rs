@@ -0,0 +1,434 @@
+use crate::commands::{ScreenshotMode, ScreenshotRaw, ScreenshotToFile};
+use crate::mpv_node::MpvNode;
+use crate::{Error, Mpv, Result, mpv_error};
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Pixel layout of a [`Frame`]. These are the formats the software renderer can
+/// produce.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 4 bytes per pixel, red at the lowest address, last byte is padding.
+    Rgb0,
+    /// 4 bytes per pixel, blue at the lowest address, last byte is padding.
+    Bgr0,
+    /// 4 bytes per pixel, first byte is padding, then blue, green, red.
+    ZeroBgr,
+    /// 4 bytes per pixel, first byte is padding, then red, green, blue.
+    ZeroRgb,
+    /// 3 bytes per pixel, red at the lowest address.
+    Rgb24,
+}
+
+impl PixelFormat {
+    /// The name mpv uses for this format.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            PixelFormat::Rgb0 => "rgb0",
+            PixelFormat::Bgr0 => "bgr0",
+            PixelFormat::ZeroBgr => "0bgr",
+            PixelFormat::ZeroRgb => "0rgb",
+            PixelFormat::Rgb24 => "rgb24",
+        }
+    }
+
+    /// Parse an mpv format name.
+    pub fn from_mpv(name: &str) -> Option<PixelFormat> {
+        match name {
+            "rgb0" | "rgba" => Some(PixelFormat::Rgb0),
+            "bgr0" | "bgra" => Some(PixelFormat::Bgr0),
+            "0bgr" | "abgr" => Some(PixelFormat::ZeroBgr),
+            "0rgb" | "argb" => Some(PixelFormat::ZeroRgb),
+            "rgb24" => Some(PixelFormat::Rgb24),
+            _ => None,
+        }
+    }
+
+    pub fn bytes_per_pixel(&self) -> usize {
+        match *self {
+            PixelFormat::Rgb24 => 3,
+            _ => 4,
+        }
+    }
+
+    // Byte offsets of the red, green and blue components within a pixel.
+    fn rgb_offsets(&self) -> [usize; 3] {
+        match *self {
+            PixelFormat::Rgb0 | PixelFormat::Rgb24 => [0, 1, 2],
+            PixelFormat::Bgr0 => [2, 1, 0],
+            PixelFormat::ZeroBgr => [3, 2, 1],
+            PixelFormat::ZeroRgb => [1, 2, 3],
+        }
+    }
+}
+
+/// An owned, packed RGB image, as rendered or captured by mpv.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    /// Bytes between the start of two lines.
+    pub stride: usize,
+    pub format: PixelFormat,
+    pub data: Vec<u8>,
+}
+
+impl Frame {
+    /// Allocate a black frame. Lines are padded to 64 bytes, which is what the
+    /// software renderer prefers.
+    pub fn new(width: u32, height: u32, format: PixelFormat) -> Frame {
+        let stride = (width as usize * format.bytes_per_pixel()).div_ceil(64) * 64;
+        Frame {
+            width,
+            height,
+            stride,
+            format,
+            data: vec![0; stride * height as usize],
+        }
+    }
+
+    /// Decode an image node, as returned by `screenshot-raw`: a map with `w`,
+    /// `h`, `stride`, `format` and the pixels in `data`.
+    pub fn from_node(node: &MpvNode) -> Result<Frame> {
+        let field = |name: &str| {
+            node.get(name)
+                .ok_or_else(|| Error::Conversion(format!("image without `{}`", name)))
+        };
+        let int = |name: &str| {
+            field(name)?
+                .i64()
+                .and_then(|int| usize::try_from(int).ok())
+                .ok_or_else(|| Error::Conversion(format!("invalid image `{}`", name)))
+        };
+        let format = field("format")?.str().unwrap_or_default();
+        let format = PixelFormat::from_mpv(format)
+            .ok_or_else(|| Error::Conversion(format!("unsupported pixel format {}", format)))?;
+        let (width, height, stride) = (int("w")?, int("h")?, int("stride")?);
+        let data = field("data")?
+            .bytes()
+            .ok_or_else(|| Error::Conversion(String::from("image data is not a byte array")))?;
+        let too_short = || Error::Conversion(String::from("image data is too short"));
+        if width
+            .checked_mul(format.bytes_per_pixel())
+            .is_none_or(|row| stride < row)
+        {
+            return Err(too_short());
+        }
+        let size = stride
+            .checked_mul(height)
+            .filter(|&size| size <= data.len())
+            .ok_or_else(too_short)?;
+
+        Ok(Frame {
+            width: width as u32,
+            height: height as u32,
+            stride,
+            format,
+            data: data[..size].to_vec(),
+        })
+    }
+
+    /// The visible pixels of line `y`, without padding.
+    pub fn row(&self, y: u32) -> &[u8] {
+        let start = y as usize * self.stride;
+        &self.data[start..start + self.width as usize * self.format.bytes_per_pixel()]
+    }
+
+    /// The red, green and blue components of the pixel at (`x`, `y`).
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        let bpp = self.format.bytes_per_pixel();
+        let px = &self.row(y)[x as usize * bpp..];
+        let [r, g, b] = self.format.rgb_offsets();
+        [px[r], px[g], px[b]]
+    }
+
+    /// Convert to tightly packed `rgb24` data.
+    pub fn to_rgb24(&self) -> Vec<u8> {
+        if self.format == PixelFormat::Rgb24 && self.stride == self.width as usize * 3 {
+            return self.data[..self.stride * self.height as usize].to_vec();
+        }
+
+        let bpp = self.format.bytes_per_pixel();
+        let [r, g, b] = self.format.rgb_offsets();
+        let mut out = Vec::with_capacity(self.width as usize * self.height as usize * 3);
+        for y in 0..self.height {
+            for px in self.row(y).chunks_exact(bpp) {
+                out.extend_from_slice(&[px[r], px[g], px[b]]);
+            }
+        }
+        out
+    }
+
+    /// A stable 64 bit FNV-1a hash of the frame size and its visible pixels.
+    /// Padding bytes and the unused fourth component are ignored, so the hash
+    /// only changes if the picture does.
+    pub fn hash(&self) -> u64 {
+        let mut hash = Fnv1a::new();
+        hash.write(&self.width.to_le_bytes());
+        hash.write(&self.height.to_le_bytes());
+        hash.write(&self.to_rgb24());
+        hash.finish()
+    }
+
+    /// Mean luma (BT.601) of the frame, from 0 to 255.
+    pub fn mean_luma(&self) -> f64 {
+        let pixels = self.width as u64 * self.height as u64;
+        if pixels == 0 {
+            return 0.;
+        }
+        let sum: u64 = self
+            .to_rgb24()
+            .chunks_exact(3)
+            .map(|px| (299 * px[0] as u64 + 587 * px[1] as u64 + 114 * px[2] as u64) / 1000)
+            .sum();
+        sum as f64 / pixels as f64
+    }
+
+    /// Encode as an 8 bit RGB PNG image. The image data is stored
+    /// uncompressed, which keeps encoding cheap and the output exact.
+    pub fn encode_png(&self) -> Vec<u8> {
+        let rgb = self.to_rgb24();
+        let line = self.width as usize * 3;
+        let mut raw = Vec::with_capacity((line + 1) * self.height as usize);
+        for y in 0..self.height as usize {
+            // filter type 0 (None)
+            raw.push(0);
+            raw.extend_from_slice(&rgb[y * line..(y + 1) * line]);
+        }
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&self.width.to_be_bytes());
+        ihdr.extend_from_slice(&self.height.to_be_bytes());
+        // bit depth 8, color type 2 (RGB), deflate, adaptive filtering, no interlace
+        ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png_chunk(&mut png, b"IHDR", &ihdr);
+        png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    /// Write the frame as a PNG file to `path`.
+    pub fn write_png<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&self.encode_png())?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Encode as a binary PPM (`P6`) image.
+    pub fn encode_ppm(&self) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        ppm.extend_from_slice(&self.to_rgb24());
+        ppm
+    }
+
+    /// Decode a binary PPM (`P6`) image with 8 bit samples, as written by
+    /// [`encode_ppm`](Frame::encode_ppm), into an `Rgb24` frame.
+    pub fn decode_ppm(data: &[u8]) -> Result<Frame> {
+        let invalid = || Error::Conversion(String::from("invalid PPM image"));
+
+        // The header is four whitespace separated fields, with optional
+        // comments, followed by a single whitespace character.
+        let mut fields = [0usize; 3];
+        let mut pos = 2;
+        if !data.starts_with(b"P6") {
+            return Err(invalid());
+        }
+        for field in &mut fields {
+            loop {
+                match data.get(pos) {
+                    Some(b'#') => {
+                        while data.get(pos).is_some_and(|&c| c != b'\n') {
+                            pos += 1;
+                        }
+                    }
+                    Some(c) if c.is_ascii_whitespace() => pos += 1,
+                    Some(_) => break,
+                    None => return Err(invalid()),
+                }
+            }
+            let start = pos;
+            while data.get(pos).is_some_and(u8::is_ascii_digit) {
+                pos += 1;
+            }
+            *field = std::str::from_utf8(&data[start..pos])
+                .ok()
+                .and_then(|n| n.parse().ok())
+                .ok_or_else(invalid)?;
+        }
+        let [width, height, max] = fields;
+        let (Ok(frame_width), Ok(frame_height)) = (u32::try_from(width), u32::try_from(height))
+        else {
+            return Err(invalid());
+        };
+        let len = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or_else(invalid)?;
+        let pixels = data
+            .get(pos + 1..)
+            .and_then(|data| data.get(..len))
+            .ok_or_else(invalid)?;
+        if max != 255 {
+            return Err(invalid());
+        }
+
+        Ok(Frame {
+            width: frame_width,
+            height: frame_height,
+            stride: width * 3,
+            format: PixelFormat::Rgb24,
+            data: pixels.to_vec(),
+        })
+    }
+
+    /// Copy `src` into this frame with its top left corner at (`x`, `y`),
+    /// converting the pixel format. Parts outside this frame are cut off.
+    pub fn blit(&mut self, src: &Frame, x: u32, y: u32) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let bpp = self.format.bytes_per_pixel();
+        let [r, g, b] = self.format.rgb_offsets();
+        let width = src.width.min(self.width.saturating_sub(x));
+        let height = src.height.min(self.height.saturating_sub(y));
+        for row in 0..height {
+            let start = (y + row) as usize * self.stride + x as usize * bpp;
+            let dst = &mut self.data[start..start + width as usize * bpp];
+            for (col, px) in dst.chunks_exact_mut(bpp).enumerate() {
+                let [sr, sg, sb] = src.pixel(col as u32, row);
+                px[r] = sr;
+                px[g] = sg;
+                px[b] = sb;
+            }
+        }
+    }
+
+    /// Convert to planar 8 bit Y'CbCr 4:4:4 (BT.601, limited range), in the
+    /// plane order used by Y4M.
+    pub fn to_yuv444(&self) -> Vec<u8> {
+        let pixels = self.width as usize * self.height as usize;
+        let mut out = vec![0; pixels * 3];
+        let (y_plane, chroma) = out.split_at_mut(pixels);
+        let (u_plane, v_plane) = chroma.split_at_mut(pixels);
+
+        for (i, px) in self.to_rgb24().chunks_exact(3).enumerate() {
+            let (r, g, b) = (px[0] as i32, px[1] as i32, px[2] as i32);
+            y_plane[i] = (((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8;
+            u_plane[i] = (((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8;
+            v_plane[i] = (((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8;
+        }
+        out
+    }
+}
+
+impl Mpv {
+    /// Take a screenshot and return its pixels, without encoding or writing it.
+    ///
+    /// Will return `Err` if there is no video to take a screenshot of.
+    pub fn screenshot_raw(&self, mode: ScreenshotMode) -> Result<Frame> {
+        self.execute(&ScreenshotRaw { mode })
+    }
+
+    /// Save a screenshot to `path`, in the format given by its extension.
+    pub fn screenshot_to_file<P: AsRef<Path>>(&self, path: P, mode: ScreenshotMode) -> Result<()> {
+        let path = path.as_ref();
+        let path = path
+            .to_str()
+            .ok_or(Error::Raw(mpv_error::InvalidParameter))?;
+        self.execute(&ScreenshotToFile::new(path).mode(mode))
+    }
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+// A zlib stream made of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 65535 * 5 + 11);
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(65535).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut n = 0;
+        while n < 256 {
+            let mut c = n as u32;
+            let mut k = 0;
+            while k < 8 {
+                c = if c & 1 != 0 {
+                    0xedb8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+                k += 1;
+            }
+            table[n] = c;
+            n += 1;
+        }
+        table
+    };
+
+    !data.iter().fold(!0u32, |crc, &byte| {
+        TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+pub(crate) struct Fnv1a(u64);
+
+impl Fnv1a {
+    pub(crate) fn new() -> Fnv1a {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
@@ -0,0 +1,210 @@
+//! Decoded metadata tags.
+
+use crate::events::{Event, PropertyData};
+use crate::mpv_node::MpvNode;
+use crate::{Error, Format, Mpv, Result};
+
+/// Tags as ordered key/value pairs, in the order mpv reports them.
+///
+/// Containers disagree on the case of tag names (`title` in Matroska, `TITLE`
+/// in Vorbis comments), so lookups ignore ASCII case.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Metadata {
+    entries: Vec<(String, String)>,
+}
+
+impl Metadata {
+    /// Decode a metadata property read as a node.
+    pub fn from_node(node: &MpvNode) -> Result<Metadata> {
+        let entries = node
+            .map()
+            .ok_or_else(|| Error::Conversion(String::from("metadata is not a map")))?
+            .iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    MpvNode::String(s) => s.clone(),
+                    MpvNode::Int64(int) => int.to_string(),
+                    MpvNode::Double(double) => double.to_string(),
+                    MpvNode::Flag(flag) => flag.to_string(),
+                    _ => {
+                        return Err(Error::Conversion(format!(
+                            "metadata value of `{}` is not a scalar",
+                            key
+                        )));
+                    }
+                };
+                Ok((key.clone(), value))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Metadata { entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The tags, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(k, v)| (&k[..], &v[..]))
+    }
+
+    /// The value of the first tag named `key`, ignoring ASCII case.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| &v[..])
+    }
+
+    fn first_of(&self, keys: &[&str]) -> Option<&str> {
+        keys.iter().find_map(|key| self.get(key))
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.get("title")
+    }
+
+    pub fn artist(&self) -> Option<&str> {
+        self.first_of(&["artist", "album_artist", "performer"])
+    }
+
+    pub fn album(&self) -> Option<&str> {
+        self.get("album")
+    }
+
+    /// The release date, or the year if no date is tagged.
+    pub fn date(&self) -> Option<&str> {
+        self.first_of(&["date", "year", "originaldate"])
+    }
+
+    /// The title of the current song of an internet radio stream.
+    pub fn icy_title(&self) -> Option<&str> {
+        self.get("icy-title")
+    }
+
+    /// The name of an internet radio station.
+    pub fn icy_name(&self) -> Option<&str> {
+        self.get("icy-name")
+    }
+}
+
+impl IntoIterator for Metadata {
+    type Item = (String, String);
+    type IntoIter = std::vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+/// A property holding metadata.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MetadataSource {
+    /// `metadata`: all tags of the current file.
+    File,
+    /// `filtered-metadata`: the tags selected by the `display-tags` option.
+    Filtered,
+    /// `chapter-metadata`: the tags of the current chapter.
+    Chapter,
+    /// `vf-metadata/<label>`: metadata exported by the video filter `@label`.
+    VideoFilter(String),
+    /// `af-metadata/<label>`: metadata exported by the audio filter `@label`.
+    AudioFilter(String),
+}
+
+impl MetadataSource {
+    pub fn property(&self) -> String {
+        match self {
+            MetadataSource::File => String::from("metadata"),
+            MetadataSource::Filtered => String::from("filtered-metadata"),
+            MetadataSource::Chapter => String::from("chapter-metadata"),
+            MetadataSource::VideoFilter(label) => format!("vf-metadata/{}", label),
+            MetadataSource::AudioFilter(label) => format!("af-metadata/{}", label),
+        }
+    }
+}
+
+impl Mpv {
+    /// Read and decode the metadata of `source`.
+    pub fn metadata(&self, source: &MetadataSource) -> Result<Metadata> {
+        Metadata::from_node(&self.get_property::<MpvNode>(&source.property())?)
+    }
+}
+
+/// Observes a metadata property and decodes its changes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MetadataObserver {
+    property: String,
+    reply_userdata: u64,
+}
+
+impl MetadataObserver {
+    /// Observe `source` on `mpv` with `reply_userdata` as observer id.
+    pub fn observe(
+        mpv: &Mpv,
+        source: &MetadataSource,
+        reply_userdata: u64,
+    ) -> Result<MetadataObserver> {
+        let property = source.property();
+        mpv.observe_property(&property, Format::Node, reply_userdata)?;
+        Ok(MetadataObserver {
+            property,
+            reply_userdata,
+        })
+    }
+
+    /// Decode `event` if it is a change of the observed property.
+    pub fn parse(&self, event: &Event<'_>) -> Option<Metadata> {
+        match event {
+            Event::PropertyChange {
+                name,
+                change: PropertyData::Node(node),
+                reply_userdata,
+            } if *reply_userdata == self.reply_userdata && *name == self.property => {
+                Metadata::from_node(node).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Reports the "now playing" title of internet radio streams, which mpv
+/// updates in `metadata` from ICY tags while the stream plays.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IcyWatcher {
+    observer: MetadataObserver,
+    title: Option<String>,
+}
+
+impl IcyWatcher {
+    /// Observe `metadata` on `mpv` with `reply_userdata` as observer id.
+    pub fn observe(mpv: &Mpv, reply_userdata: u64) -> Result<IcyWatcher> {
+        Ok(IcyWatcher {
+            observer: MetadataObserver::observe(mpv, &MetadataSource::File, reply_userdata)?,
+            title: None,
+        })
+    }
+
+    /// Update the title from an event. Returns whether the title changed;
+    /// changes of other tags are ignored.
+    pub fn handle_event(&mut self, event: &Event<'_>) -> bool {
+        let Some(metadata) = self.observer.parse(event) else {
+            return false;
+        };
+        let title = metadata.icy_title();
+        if title == self.title.as_deref() {
+            return false;
+        }
+        self.title = title.map(str::to_owned);
+        true
+    }
+
+    /// The current title, if the stream sent one.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+}
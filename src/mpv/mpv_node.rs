@@ -0,0 +1,409 @@
+use crate::{Error, Format, GetData, Mpv, Result, SetData, mpv::mpv_err, mpv_error, mpv_format};
+
+use std::ffi::CString;
+use std::mem::MaybeUninit;
+use std::os::raw as ctype;
+use std::{ptr, slice};
+
+#[cfg(feature = "serde")]
+mod de;
+#[cfg(feature = "serde")]
+mod ser;
+
+#[cfg(feature = "serde")]
+pub use self::{de::from_node, ser::to_node};
+
+/// An owned copy of an `mpv_node`, mpv's generic structured value. Maps keep
+/// the order of their entries.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum MpvNode {
+    #[default]
+    None,
+    String(String),
+    Flag(bool),
+    Int64(i64),
+    Double(f64),
+    Array(Vec<MpvNode>),
+    Map(Vec<(String, MpvNode)>),
+    /// Raw bytes, e.g. the image data returned by `screenshot-raw`.
+    ByteArray(Vec<u8>),
+}
+
+impl MpvNode {
+    pub fn str(&self) -> Option<&str> {
+        match self {
+            MpvNode::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn bool(&self) -> Option<bool> {
+        match *self {
+            MpvNode::Flag(flag) => Some(flag),
+            _ => None,
+        }
+    }
+
+    pub fn i64(&self) -> Option<i64> {
+        match *self {
+            MpvNode::Int64(int) => Some(int),
+            _ => None,
+        }
+    }
+
+    /// The value of a `Double` node, or of an `Int64` node converted to `f64`.
+    pub fn f64(&self) -> Option<f64> {
+        match *self {
+            MpvNode::Double(double) => Some(double),
+            MpvNode::Int64(int) => Some(int as f64),
+            _ => None,
+        }
+    }
+
+    pub fn array(&self) -> Option<&[MpvNode]> {
+        match self {
+            MpvNode::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    pub fn map(&self) -> Option<&[(String, MpvNode)]> {
+        match self {
+            MpvNode::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    pub fn bytes(&self) -> Option<&[u8]> {
+        match self {
+            MpvNode::ByteArray(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// The value of the first entry named `key`, if this is a map.
+    pub fn get(&self, key: &str) -> Option<&MpvNode> {
+        self.map()?
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn is_none(&self) -> bool {
+        matches!(self, MpvNode::None)
+    }
+
+    /// Build a `Map` node from `(key, value)` pairs.
+    pub fn from_map<K: Into<String>, V: Into<MpvNode>>(
+        entries: impl IntoIterator<Item = (K, V)>,
+    ) -> MpvNode {
+        MpvNode::Map(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+
+    // SAFETY: `node` must be a valid node, as returned by mpv.
+    pub(crate) unsafe fn from_raw(node: &libmpv2_sys::mpv_node) -> Result<MpvNode> {
+        unsafe {
+            Ok(match node.format {
+                mpv_format::None => MpvNode::None,
+                mpv_format::String | mpv_format::OsdString => {
+                    MpvNode::String(mpv_cstr_to_str!(node.u.string)?.to_owned())
+                }
+                mpv_format::Flag => MpvNode::Flag(node.u.flag != 0),
+                mpv_format::Int64 => MpvNode::Int64(node.u.int64),
+                mpv_format::Double => MpvNode::Double(node.u.double_),
+                mpv_format::Array => MpvNode::Array(
+                    raw_list(node.u.list)
+                        .0
+                        .iter()
+                        .map(|value| MpvNode::from_raw(value))
+                        .collect::<Result<_>>()?,
+                ),
+                mpv_format::Map => {
+                    let (values, keys) = raw_list(node.u.list);
+                    keys.iter()
+                        .zip(values)
+                        .map(|(key, value)| {
+                            Ok((
+                                mpv_cstr_to_str!(*key)?.to_owned(),
+                                MpvNode::from_raw(value)?,
+                            ))
+                        })
+                        .collect::<Result<_>>()
+                        .map(MpvNode::Map)?
+                }
+                mpv_format::ByteArray => {
+                    let ba = &*node.u.ba;
+                    MpvNode::ByteArray(if ba.data.is_null() {
+                        Vec::new()
+                    } else {
+                        slice::from_raw_parts(ba.data as *const u8, ba.size).to_vec()
+                    })
+                }
+                _ => return Err(Error::Raw(mpv_error::UnknownFormat)),
+            })
+        }
+    }
+}
+
+// SAFETY: `list` must be null or a valid list of a node returned by mpv.
+unsafe fn raw_list<'a>(
+    list: *const libmpv2_sys::mpv_node_list,
+) -> (&'a [libmpv2_sys::mpv_node], &'a [*mut ctype::c_char]) {
+    if list.is_null() {
+        return (&[], &[]);
+    }
+    unsafe {
+        let list = &*list;
+        let len = list.num as usize;
+        let values = if list.values.is_null() {
+            &[][..]
+        } else {
+            slice::from_raw_parts(list.values, len)
+        };
+        let keys = if list.keys.is_null() {
+            &[][..]
+        } else {
+            slice::from_raw_parts(list.keys, len)
+        };
+        (values, keys)
+    }
+}
+
+/// Owns all memory referenced by an `mpv_node` built from an `MpvNode`, so it
+/// can be passed to mpv.
+#[derive(Default)]
+struct RawNodeStorage {
+    strings: Vec<CString>,
+    bytes: Vec<Box<[u8]>>,
+    values: Vec<Box<[libmpv2_sys::mpv_node]>>,
+    keys: Vec<Box<[*mut ctype::c_char]>>,
+    // Boxed so the lists don't move when the `Vec` grows.
+    #[allow(clippy::vec_box)]
+    lists: Vec<Box<libmpv2_sys::mpv_node_list>>,
+    #[allow(clippy::vec_box)]
+    byte_arrays: Vec<Box<libmpv2_sys::mpv_byte_array>>,
+}
+
+impl RawNodeStorage {
+    fn string(&mut self, s: &str) -> Result<*mut ctype::c_char> {
+        let s = CString::new(s)?;
+        let ptr = s.as_ptr() as *mut _;
+        self.strings.push(s);
+        Ok(ptr)
+    }
+
+    fn node(&mut self, node: &MpvNode) -> Result<libmpv2_sys::mpv_node> {
+        let mut raw: libmpv2_sys::mpv_node = unsafe { MaybeUninit::zeroed().assume_init() };
+        match node {
+            MpvNode::None => raw.format = mpv_format::None,
+            MpvNode::String(s) => {
+                raw.format = mpv_format::String;
+                raw.u.string = self.string(s)?;
+            }
+            MpvNode::Flag(flag) => {
+                raw.format = mpv_format::Flag;
+                raw.u.flag = *flag as _;
+            }
+            MpvNode::Int64(int) => {
+                raw.format = mpv_format::Int64;
+                raw.u.int64 = *int;
+            }
+            MpvNode::Double(double) => {
+                raw.format = mpv_format::Double;
+                raw.u.double_ = *double;
+            }
+            MpvNode::Array(array) => {
+                raw.format = mpv_format::Array;
+                raw.u.list = self.list(array.iter(), None)?;
+            }
+            MpvNode::Map(map) => {
+                raw.format = mpv_format::Map;
+                let keys = map
+                    .iter()
+                    .map(|(key, _)| self.string(key))
+                    .collect::<Result<Box<[_]>>>()?;
+                raw.u.list = self.list(map.iter().map(|(_, value)| value), Some(keys))?;
+            }
+            MpvNode::ByteArray(bytes) => {
+                raw.format = mpv_format::ByteArray;
+                raw.u.ba = self.byte_array(bytes);
+            }
+        }
+        Ok(raw)
+    }
+
+    fn byte_array(&mut self, bytes: &[u8]) -> *mut libmpv2_sys::mpv_byte_array {
+        let mut bytes: Box<[u8]> = bytes.into();
+        let mut ba = Box::new(libmpv2_sys::mpv_byte_array {
+            data: bytes.as_mut_ptr() as *mut _,
+            size: bytes.len(),
+        });
+        self.bytes.push(bytes);
+
+        let ptr = &mut *ba as *mut _;
+        self.byte_arrays.push(ba);
+        ptr
+    }
+
+    fn list<'a>(
+        &mut self,
+        values: impl Iterator<Item = &'a MpvNode>,
+        keys: Option<Box<[*mut ctype::c_char]>>,
+    ) -> Result<*mut libmpv2_sys::mpv_node_list> {
+        let mut values = values
+            .map(|value| self.node(value))
+            .collect::<Result<Box<[_]>>>()?;
+        let mut list = Box::new(libmpv2_sys::mpv_node_list {
+            num: values.len() as _,
+            values: values.as_mut_ptr(),
+            keys: ptr::null_mut(),
+        });
+        if let Some(mut keys) = keys {
+            list.keys = keys.as_mut_ptr();
+            self.keys.push(keys);
+        }
+        self.values.push(values);
+
+        let ptr = &mut *list as *mut _;
+        self.lists.push(list);
+        Ok(ptr)
+    }
+}
+
+impl MpvNode {
+    // Call `fun` with a pointer to an `mpv_node` equal to `self`, which is
+    // valid for the duration of the call.
+    pub(crate) fn with_raw<T, F: FnOnce(*mut libmpv2_sys::mpv_node) -> Result<T>>(
+        &self,
+        fun: F,
+    ) -> Result<T> {
+        let mut storage = RawNodeStorage::default();
+        let mut raw = storage.node(self)?;
+        fun(&mut raw)
+    }
+}
+
+unsafe impl GetData for MpvNode {
+    fn get_from_c_void<T, F: FnMut(*mut ctype::c_void) -> Result<T>>(
+        mut fun: F,
+    ) -> Result<MpvNode> {
+        let mut raw = MaybeUninit::<libmpv2_sys::mpv_node>::uninit();
+        fun(raw.as_mut_ptr() as *mut _)?;
+
+        let mut raw = unsafe { raw.assume_init() };
+        let ret = unsafe { MpvNode::from_raw(&raw) };
+        unsafe { libmpv2_sys::mpv_free_node_contents(&mut raw) };
+        ret
+    }
+
+    fn get_format() -> Format {
+        Format::Node
+    }
+}
+
+unsafe impl SetData for MpvNode {
+    fn call_as_c_void<T, F: FnMut(*mut ctype::c_void) -> Result<T>>(self, mut fun: F) -> Result<T> {
+        self.with_raw(|raw| fun(raw as *mut _))
+    }
+
+    fn get_format() -> Format {
+        Format::Node
+    }
+}
+
+impl From<&str> for MpvNode {
+    fn from(s: &str) -> MpvNode {
+        MpvNode::String(s.to_owned())
+    }
+}
+
+impl From<String> for MpvNode {
+    fn from(s: String) -> MpvNode {
+        MpvNode::String(s)
+    }
+}
+
+impl From<bool> for MpvNode {
+    fn from(flag: bool) -> MpvNode {
+        MpvNode::Flag(flag)
+    }
+}
+
+impl From<i64> for MpvNode {
+    fn from(int: i64) -> MpvNode {
+        MpvNode::Int64(int)
+    }
+}
+
+impl From<f64> for MpvNode {
+    fn from(double: f64) -> MpvNode {
+        MpvNode::Double(double)
+    }
+}
+
+impl<T: Into<MpvNode>> From<Vec<T>> for MpvNode {
+    fn from(array: Vec<T>) -> MpvNode {
+        MpvNode::Array(array.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<MpvNode>> From<Option<T>> for MpvNode {
+    fn from(value: Option<T>) -> MpvNode {
+        value.map_or(MpvNode::None, Into::into)
+    }
+}
+
+impl Mpv {
+    /// Send a command to the player, passing the arguments as a node, and
+    /// return its result.
+    ///
+    /// `args` is either an array whose first element is the command name,
+    /// followed by its arguments, or a map with a `name` entry and the named
+    /// arguments. Unlike [command](Mpv::command), arguments keep their types and
+    /// don't have to be quoted.
+    ///
+    /// Will return `Err` if the command failed, in which case mpv's error is
+    /// returned.
+    pub fn command_node(&self, args: &MpvNode) -> Result<MpvNode> {
+        crate::mpv::callback::assert_outside("Mpv::command_node");
+        args.with_raw(|raw| {
+            MpvNode::get_from_c_void(|result| {
+                mpv_err((), unsafe {
+                    libmpv2_sys::mpv_command_node(self.ctx.as_ptr(), raw, result as *mut _)
+                })
+            })
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Mpv {
+    /// Read a property as a node and deserialize it into `T`, e.g. a `Vec` of
+    /// structs for `track-list`.
+    ///
+    /// Will return `Err` if the property can't be read, or if it doesn't match
+    /// the structure of `T`.
+    pub fn get_property_as<T: serde::de::DeserializeOwned>(&self, name: &str) -> Result<T> {
+        from_node(self.get_property::<MpvNode>(name)?)
+    }
+
+    /// Serialize `value` into a node and set the property `name` to it.
+    pub fn set_property_from<T: serde::Serialize + ?Sized>(
+        &self,
+        name: &str,
+        value: &T,
+    ) -> Result<()> {
+        self.set_property(name, to_node(value)?)
+    }
+
+    /// Like [command_node](Mpv::command_node), but with the arguments
+    /// serialized from `args`.
+    pub fn command_node_from<T: serde::Serialize + ?Sized>(&self, args: &T) -> Result<MpvNode> {
+        self.command_node(&to_node(args)?)
+    }
+}
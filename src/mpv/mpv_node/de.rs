@@ -0,0 +1,137 @@
+use super::MpvNode;
+use crate::{Error, Result};
+
+use serde::de::value::{MapDeserializer, SeqDeserializer, StringDeserializer};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use std::fmt::Display;
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Error {
+        Error::Conversion(msg.to_string())
+    }
+}
+
+/// Convert an `MpvNode` to any `DeserializeOwned` value.
+///
+/// Enums are accepted either as a string naming a unit variant, or as a map
+/// with a single entry whose key is the variant name.
+pub fn from_node<T: DeserializeOwned>(node: MpvNode) -> Result<T> {
+    T::deserialize(node)
+}
+
+impl<'de> IntoDeserializer<'de, Error> for MpvNode {
+    type Deserializer = MpvNode;
+
+    fn into_deserializer(self) -> MpvNode {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for MpvNode {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            MpvNode::None => visitor.visit_unit(),
+            MpvNode::String(s) => visitor.visit_string(s),
+            MpvNode::Flag(flag) => visitor.visit_bool(flag),
+            MpvNode::Int64(int) => visitor.visit_i64(int),
+            MpvNode::Double(double) => visitor.visit_f64(double),
+            MpvNode::Array(array) => {
+                let mut seq = SeqDeserializer::new(array.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            MpvNode::Map(map) => {
+                let mut map = MapDeserializer::new(map.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+            MpvNode::ByteArray(bytes) => visitor.visit_byte_buf(bytes),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            MpvNode::None => visitor.visit_none(),
+            node => visitor.visit_some(node),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self {
+            MpvNode::String(variant) => {
+                visitor.visit_enum(StringDeserializer::<Error>::new(variant))
+            }
+            MpvNode::Map(mut map) if map.len() == 1 => {
+                let (variant, value) = map.remove(0);
+                visitor.visit_enum(EnumAccess { variant, value })
+            }
+            other => Err(Error::Conversion(format!(
+                "expected an enum variant, found {:?}",
+                other
+            ))),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct EnumAccess {
+    variant: String,
+    value: MpvNode,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess {
+    type Error = Error;
+    type Variant = MpvNode;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, MpvNode)> {
+        let variant = seed.deserialize(StringDeserializer::<Error>::new(self.variant))?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for MpvNode {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
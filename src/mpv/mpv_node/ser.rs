@@ -0,0 +1,475 @@
+use super::MpvNode;
+use crate::{Error, Result};
+
+use serde::ser::{self, Impossible, Serialize};
+use std::fmt::Display;
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Error {
+        Error::Conversion(msg.to_string())
+    }
+}
+
+/// Convert any `Serialize` value to an `MpvNode`.
+///
+/// Structs and maps become `Map` nodes, sequences and tuples `Array` nodes,
+/// `None` and `()` the `None` node. Unsigned integers that don't fit into an
+/// `i64` are rejected.
+pub fn to_node<T: Serialize + ?Sized>(value: &T) -> Result<MpvNode> {
+    value.serialize(Serializer)
+}
+
+struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = MpvNode;
+    type Error = Error;
+
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeVariant<SerializeArray>;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeVariant<SerializeMap>;
+
+    fn serialize_bool(self, v: bool) -> Result<MpvNode> {
+        Ok(MpvNode::Flag(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<MpvNode> {
+        Ok(MpvNode::Int64(v as _))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<MpvNode> {
+        Ok(MpvNode::Int64(v as _))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<MpvNode> {
+        Ok(MpvNode::Int64(v as _))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<MpvNode> {
+        Ok(MpvNode::Int64(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<MpvNode> {
+        Ok(MpvNode::Int64(v as _))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<MpvNode> {
+        Ok(MpvNode::Int64(v as _))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<MpvNode> {
+        Ok(MpvNode::Int64(v as _))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<MpvNode> {
+        i64::try_from(v)
+            .map(MpvNode::Int64)
+            .map_err(|_| Error::Conversion(format!("{} does not fit into an i64", v)))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<MpvNode> {
+        Ok(MpvNode::Double(v as _))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<MpvNode> {
+        Ok(MpvNode::Double(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<MpvNode> {
+        Ok(MpvNode::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<MpvNode> {
+        Ok(MpvNode::String(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<MpvNode> {
+        Ok(MpvNode::ByteArray(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<MpvNode> {
+        Ok(MpvNode::None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<MpvNode> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<MpvNode> {
+        Ok(MpvNode::None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<MpvNode> {
+        Ok(MpvNode::None)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<MpvNode> {
+        Ok(MpvNode::String(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<MpvNode> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<MpvNode> {
+        Ok(MpvNode::Map(vec![(variant.to_owned(), to_node(value)?)]))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeArray> {
+        Ok(SerializeArray(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeArray> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeArray> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeArray>> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeMap> {
+        Ok(SerializeMap {
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeMap> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeMap>> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+struct SerializeArray(Vec<MpvNode>);
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = MpvNode;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.0.push(to_node(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<MpvNode> {
+        Ok(MpvNode::Array(self.0))
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = MpvNode;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<MpvNode> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = MpvNode;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<MpvNode> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct SerializeMap {
+    entries: Vec<(String, MpvNode)>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = MpvNode;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::Conversion(String::from("map value without a key")))?;
+        self.entries.push((key, to_node(value)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<MpvNode> {
+        Ok(MpvNode::Map(self.entries))
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = MpvNode;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.entries.push((key.to_owned(), to_node(value)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<MpvNode> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+// Wraps the content of an enum variant in a single entry map named after it.
+struct SerializeVariant<T> {
+    variant: &'static str,
+    inner: T,
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeArray> {
+    type Ok = MpvNode;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<MpvNode> {
+        let inner = ser::SerializeSeq::end(self.inner)?;
+        Ok(MpvNode::Map(vec![(self.variant.to_owned(), inner)]))
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeMap> {
+    type Ok = MpvNode;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<MpvNode> {
+        let inner = ser::SerializeMap::end(self.inner)?;
+        Ok(MpvNode::Map(vec![(self.variant.to_owned(), inner)]))
+    }
+}
+
+// Node map keys are strings. Numbers, chars and unit variants are accepted as
+// keys and converted.
+struct KeySerializer;
+
+fn key_error() -> Error {
+    Error::Conversion(String::from("map keys must be strings"))
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = Error;
+
+    type SerializeSeq = Impossible<String, Error>;
+    type SerializeTuple = Impossible<String, Error>;
+    type SerializeTupleStruct = Impossible<String, Error>;
+    type SerializeTupleVariant = Impossible<String, Error>;
+    type SerializeMap = Impossible<String, Error>;
+    type SerializeStruct = Impossible<String, Error>;
+    type SerializeStructVariant = Impossible<String, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<String> {
+        Err(key_error())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<String> {
+        Err(key_error())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String> {
+        Ok(v.to_owned())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String> {
+        Err(key_error())
+    }
+
+    fn serialize_none(self) -> Result<String> {
+        Err(key_error())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<String> {
+        Err(key_error())
+    }
+
+    fn serialize_unit(self) -> Result<String> {
+        Err(key_error())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String> {
+        Err(key_error())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String> {
+        Err(key_error())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(key_error())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(key_error())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(key_error())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(key_error())
+    }
+}
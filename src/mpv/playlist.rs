@@ -0,0 +1,277 @@
+//! Typed access to the playlist.
+//!
+//! [`Mpv::playlist`] returns a [`Playlist`] view that reads and edits the
+//! playlist directly. A [`PlaylistMirror`] keeps a local copy up to date from
+//! the events of an observing client, so reading it doesn't go through mpv.
+
+use crate::commands::{
+    LoadFile, LoadFileMode, PlaylistClear, PlaylistIndex, PlaylistMove, PlaylistPlayIndex,
+    PlaylistRemove, PlaylistShuffle, PlaylistUnshuffle,
+};
+use crate::events::{Event, PropertyData};
+use crate::mpv_node::MpvNode;
+use crate::{Error, Format, Mpv, Result, mpv_error};
+
+/// An entry of the `playlist` property.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlaylistEntry {
+    /// Unique id of the entry. Unlike its index, it doesn't change when the
+    /// playlist is edited.
+    pub id: i64,
+    pub filename: String,
+    pub title: Option<String>,
+    /// Whether this is the current entry.
+    pub current: bool,
+    /// Whether this entry is being played. Differs from `current` while
+    /// switching files.
+    pub playing: bool,
+}
+
+impl PlaylistEntry {
+    /// Decode an entry of the `playlist` property read as a node.
+    pub fn from_node(node: &MpvNode) -> Result<PlaylistEntry> {
+        let field = |key| {
+            node.get(key)
+                .ok_or_else(|| Error::Conversion(format!("playlist entry without `{}`", key)))
+        };
+        let flag = |key| node.get(key).and_then(MpvNode::bool).unwrap_or(false);
+
+        Ok(PlaylistEntry {
+            id: field("id")?.i64().ok_or_else(|| {
+                Error::Conversion(String::from("playlist entry id is not an int"))
+            })?,
+            filename: field("filename")?
+                .str()
+                .ok_or_else(|| {
+                    Error::Conversion(String::from("playlist entry filename is not a string"))
+                })?
+                .to_owned(),
+            title: node.get("title").and_then(MpvNode::str).map(str::to_owned),
+            current: flag("current"),
+            playing: flag("playing"),
+        })
+    }
+}
+
+fn decode_entries(node: &MpvNode) -> Result<Vec<PlaylistEntry>> {
+    node.array()
+        .ok_or_else(|| Error::Conversion(String::from("playlist is not an array")))?
+        .iter()
+        .map(PlaylistEntry::from_node)
+        .collect()
+}
+
+/// A view of the playlist of an `Mpv` instance, returned by [`Mpv::playlist`].
+///
+/// Every method talks to mpv, so the playlist may change between two calls if
+/// other clients edit it. Indices are zero based.
+#[derive(Clone, Copy)]
+pub struct Playlist<'a> {
+    mpv: &'a Mpv,
+}
+
+impl Mpv {
+    pub fn playlist(&self) -> Playlist<'_> {
+        Playlist { mpv: self }
+    }
+}
+
+impl Playlist<'_> {
+    /// Read all entries.
+    pub fn entries(&self) -> Result<Vec<PlaylistEntry>> {
+        decode_entries(&self.mpv.get_property::<MpvNode>("playlist")?)
+    }
+
+    pub fn len(&self) -> Result<usize> {
+        Ok(self.mpv.get_property::<i64>("playlist-count")? as usize)
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// The entry at `index`.
+    pub fn get(&self, index: usize) -> Result<PlaylistEntry> {
+        PlaylistEntry::from_node(
+            &self
+                .mpv
+                .get_property::<MpvNode>(&format!("playlist/{}", index))?,
+        )
+    }
+
+    /// The current entry, if any.
+    pub fn current(&self) -> Result<Option<PlaylistEntry>> {
+        match self.mpv.get_property::<i64>("playlist-current-pos")? {
+            pos if pos < 0 => Ok(None),
+            pos => self.get(pos as usize).map(Some),
+        }
+    }
+
+    /// The index of the entry with the given id.
+    pub fn index_of(&self, id: i64) -> Result<Option<usize>> {
+        Ok(self.entries()?.iter().position(|entry| entry.id == id))
+    }
+
+    // Older mpv versions don't report the id of loaded entries. Fall back to
+    // reading the entry at the index it was inserted at.
+    fn load(&self, command: LoadFile, index: impl FnOnce() -> Result<usize>) -> Result<i64> {
+        match self.mpv.execute(&command)? {
+            Some(id) => Ok(id),
+            None => Ok(self.get(index()?)?.id),
+        }
+    }
+
+    /// Append `url` to the playlist and return the id of its entry.
+    pub fn append<S: Into<String>>(&self, url: S) -> Result<i64> {
+        self.load(LoadFile::new(url).mode(LoadFileMode::Append), || {
+            self.len()?
+                .checked_sub(1)
+                .ok_or(Error::Raw(mpv_error::PropertyUnavailable))
+        })
+    }
+
+    /// Insert `url` before the entry at `index` and return the id of its entry.
+    /// Requires mpv 0.38.
+    pub fn insert_at<S: Into<String>>(&self, index: usize, url: S) -> Result<i64> {
+        self.load(
+            LoadFile::new(url).mode(LoadFileMode::InsertAt(index as i64)),
+            || Ok(index),
+        )
+    }
+
+    /// Move the entry at index `from` so it ends up before the entry that was
+    /// at index `to`. Returns the id of the moved entry.
+    pub fn move_entry(&self, from: usize, to: usize) -> Result<i64> {
+        let id = self.get(from)?.id;
+        self.mpv.execute(&PlaylistMove {
+            from: from as i64,
+            to: to as i64,
+        })?;
+        Ok(id)
+    }
+
+    /// Remove the entry at `index` and return its id. Removing the current
+    /// entry stops it and plays the next one.
+    pub fn remove(&self, index: usize) -> Result<i64> {
+        let id = self.get(index)?.id;
+        self.mpv
+            .execute(&PlaylistRemove(PlaylistIndex::Index(index as i64)))?;
+        Ok(id)
+    }
+
+    /// Remove all entries except the current one.
+    pub fn clear(&self) -> Result<()> {
+        self.mpv.execute(&PlaylistClear)
+    }
+
+    pub fn shuffle(&self) -> Result<()> {
+        self.mpv.execute(&PlaylistShuffle)
+    }
+
+    pub fn unshuffle(&self) -> Result<()> {
+        self.mpv.execute(&PlaylistUnshuffle)
+    }
+
+    /// Start playing the entry at `index`.
+    pub fn play(&self, index: usize) -> Result<()> {
+        self.mpv
+            .execute(&PlaylistPlayIndex(PlaylistIndex::Index(index as i64)))
+    }
+}
+
+/// A local copy of the playlist, kept up to date from the events of a client
+/// observing `playlist`.
+///
+/// ```no_run
+/// # use libmpv2::{Mpv, playlist::PlaylistMirror};
+/// # fn main() -> libmpv2::Result<()> {
+/// let mut mpv = Mpv::new()?;
+/// let mut mirror = PlaylistMirror::observe(&mpv, 1)?;
+/// while let Some(event) = mpv.wait_event(-1.) {
+///     mirror.handle_event(&event?);
+///     println!("{:?}", mirror.playing());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PlaylistMirror {
+    entries: Vec<PlaylistEntry>,
+    playing: Option<i64>,
+    reply_userdata: u64,
+}
+
+impl PlaylistMirror {
+    /// Observe `playlist` on `mpv` with `reply_userdata` as observer id, and
+    /// read its current state.
+    pub fn observe(mpv: &Mpv, reply_userdata: u64) -> Result<PlaylistMirror> {
+        let entries = mpv.playlist().entries()?;
+        mpv.observe_property("playlist", Format::Node, reply_userdata)?;
+        Ok(PlaylistMirror {
+            playing: entries.iter().find(|entry| entry.playing).map(|e| e.id),
+            entries,
+            reply_userdata,
+        })
+    }
+
+    /// Update the mirror from an event. Returns whether the event changed it.
+    ///
+    /// An entry that failed to decode leaves the mirror unchanged.
+    pub fn handle_event(&mut self, event: &Event<'_>) -> bool {
+        match event {
+            Event::PropertyChange {
+                name: "playlist",
+                change: PropertyData::Node(node),
+                reply_userdata,
+            } if *reply_userdata == self.reply_userdata => match decode_entries(node) {
+                Ok(entries) => {
+                    self.entries = entries;
+                    true
+                }
+                Err(_) => false,
+            },
+            Event::StartFile { playlist_entry_id } => {
+                self.playing = Some(*playlist_entry_id);
+                true
+            }
+            Event::EndFile {
+                playlist_entry_id, ..
+            } if self.playing == Some(*playlist_entry_id) => {
+                self.playing = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn entries(&self) -> &[PlaylistEntry] {
+        &self.entries
+    }
+
+    /// The entry with the given id.
+    pub fn get(&self, id: i64) -> Option<&PlaylistEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// The index of the entry with the given id.
+    pub fn index_of(&self, id: i64) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.id == id)
+    }
+
+    /// The current entry, according to the last `playlist` change.
+    pub fn current(&self) -> Option<&PlaylistEntry> {
+        self.entries.iter().find(|entry| entry.current)
+    }
+
+    /// The entry of the file being played, according to the last `StartFile`
+    /// and `EndFile` events.
+    pub fn playing(&self) -> Option<&PlaylistEntry> {
+        self.get(self.playing?)
+    }
+
+    /// The id of the entry being played, even if it was removed from the
+    /// playlist since.
+    pub fn playing_id(&self) -> Option<i64> {
+        self.playing
+    }
+}
@@ -0,0 +1,339 @@
+//! A pool of headless `Mpv` instances for running jobs in parallel.
+//!
+//! Creating an instance takes a noticeable amount of time. An [`MpvPool`] keeps
+//! initialized instances around and resets them between jobs:
+//!
+//! ```no_run
+//! # use libmpv2::{pool::MpvPool, probe::probe_on};
+//! # use std::time::Duration;
+//! # fn main() -> libmpv2::Result<()> {
+//! let pool = MpvPool::new(4)?;
+//! std::thread::scope(|scope| {
+//!     for path in ["a.mkv", "b.mkv", "c.mkv"] {
+//!         let pool = &pool;
+//!         scope.spawn(move || {
+//!             let duration = pool
+//!                 .run(|mpv| probe_on(mpv, path, Duration::from_secs(10)))
+//!                 .map(|info| info.duration);
+//!             println!("{}: {:?}", path, duration);
+//!         });
+//!     }
+//! });
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::commands::Stop;
+use crate::{Error, Mpv, Result, mpv_error};
+
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Options of the instances in an [`MpvPool`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolOptions {
+    /// Number of instances. Defaults to the number of CPUs.
+    pub size: usize,
+    /// Options set on every instance when it is created. Defaults to the
+    /// `null` video and audio outputs, without config files, scripts or
+    /// youtube-dl.
+    pub options: Vec<(String, String)>,
+    /// Properties restored to their value after creation when an instance
+    /// is returned to the pool.
+    pub restore: Vec<String>,
+}
+
+impl Default for PoolOptions {
+    fn default() -> PoolOptions {
+        let options = [
+            ("config", "no"),
+            ("load-scripts", "no"),
+            ("ytdl", "no"),
+            ("vo", "null"),
+            ("ao", "null"),
+            ("idle", "yes"),
+        ];
+        let restore = [
+            "pause",
+            "speed",
+            "volume",
+            "mute",
+            "loop-file",
+            "start",
+            "end",
+            "vid",
+            "aid",
+            "sid",
+            "vf",
+            "af",
+        ];
+        PoolOptions {
+            size: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            options: options
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            restore: restore.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+}
+
+impl PoolOptions {
+    pub fn size(mut self, size: usize) -> PoolOptions {
+        self.size = size;
+        self
+    }
+
+    pub fn option<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> PoolOptions {
+        self.options.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn restore<S: Into<String>>(mut self, name: S) -> PoolOptions {
+        self.restore.push(name.into());
+        self
+    }
+}
+
+// An instance waiting in the pool, with the values to restore.
+struct Idle {
+    mpv: Mpv,
+    defaults: Vec<(String, String)>,
+}
+
+struct State {
+    idle: Vec<Idle>,
+    // Instances in the pool or handed out.
+    alive: usize,
+}
+
+struct Inner {
+    options: PoolOptions,
+    state: Mutex<State>,
+    returned: Condvar,
+}
+
+/// A pool of headless `Mpv` instances. Cloning it returns a handle to the same
+/// pool.
+#[derive(Clone)]
+pub struct MpvPool {
+    inner: Arc<Inner>,
+}
+
+impl MpvPool {
+    /// Create a pool of `size` instances with the default [`PoolOptions`].
+    pub fn new(size: usize) -> Result<MpvPool> {
+        MpvPool::with_options(PoolOptions::default().size(size))
+    }
+
+    /// Create a pool and initialize all of its instances.
+    ///
+    /// Returns `Error::Raw(mpv_error::InvalidParameter)` if the size is 0, as
+    /// taking an instance from such a pool would wait forever.
+    pub fn with_options(options: PoolOptions) -> Result<MpvPool> {
+        if options.size == 0 {
+            return Err(Error::Raw(mpv_error::InvalidParameter));
+        }
+        let pool = MpvPool {
+            inner: Arc::new(Inner {
+                options,
+                state: Mutex::new(State {
+                    idle: Vec::new(),
+                    alive: 0,
+                }),
+                returned: Condvar::new(),
+            }),
+        };
+        for _ in 0..pool.inner.options.size {
+            let idle = pool.create()?;
+            let mut state = pool.lock();
+            state.idle.push(idle);
+            state.alive += 1;
+        }
+        Ok(pool)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // The state is consistent after every update, so ignore poisoning.
+        self.inner.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn create(&self) -> Result<Idle> {
+        let options = &self.inner.options;
+        let mpv = Mpv::with_initializer(|init| {
+            for (name, value) in &options.options {
+                init.set_property(name, &value[..])?;
+            }
+            Ok(())
+        })?;
+        let defaults = options
+            .restore
+            .iter()
+            .map(|name| Ok((name.clone(), mpv.get_property::<String>(name)?)))
+            .collect::<Result<_>>()?;
+        Ok(Idle { mpv, defaults })
+    }
+
+    pub fn size(&self) -> usize {
+        self.inner.options.size
+    }
+
+    /// Number of instances waiting in the pool.
+    pub fn idle(&self) -> usize {
+        self.lock().idle.len()
+    }
+
+    /// Take an instance from the pool, waiting until one is returned if all are
+    /// in use. Instances discarded before are replaced.
+    pub fn get(&self) -> Result<PooledMpv> {
+        self.get_until(None)
+    }
+
+    /// Like [get](MpvPool::get), but returns `Error::Timeout` if no instance
+    /// is returned within `timeout`.
+    pub fn get_timeout(&self, timeout: Duration) -> Result<PooledMpv> {
+        self.get_until(Some(Instant::now() + timeout))
+    }
+
+    fn get_until(&self, deadline: Option<Instant>) -> Result<PooledMpv> {
+        let mut state = self.lock();
+        loop {
+            if let Some(idle) = state.idle.pop() {
+                return Ok(self.guard(idle));
+            }
+            if state.alive < self.inner.options.size {
+                state.alive += 1;
+                drop(state);
+                return match self.create() {
+                    Ok(idle) => Ok(self.guard(idle)),
+                    Err(e) => {
+                        self.lock().alive -= 1;
+                        self.inner.returned.notify_one();
+                        Err(e)
+                    }
+                };
+            }
+            state = match deadline {
+                None => self
+                    .inner
+                    .returned
+                    .wait(state)
+                    .unwrap_or_else(|e| e.into_inner()),
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    if timeout.is_zero() {
+                        return Err(Error::Timeout);
+                    }
+                    self.inner
+                        .returned
+                        .wait_timeout(state, timeout)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+            };
+        }
+    }
+
+    fn guard(&self, idle: Idle) -> PooledMpv {
+        PooledMpv {
+            idle: Some(idle),
+            pool: self.clone(),
+            discard: false,
+        }
+    }
+
+    /// Run `job` on an instance from the pool. If it fails, the instance is
+    /// discarded instead of being returned.
+    pub fn run<T, F: FnOnce(&mut Mpv) -> Result<T>>(&self, job: F) -> Result<T> {
+        let mut mpv = self.get()?;
+        let ret = job(&mut mpv);
+        if ret.is_err() {
+            mpv.discard();
+        }
+        ret
+    }
+
+    fn put_back(&self, idle: Option<Idle>) {
+        let mut state = self.lock();
+        match idle {
+            Some(idle) => state.idle.push(idle),
+            None => state.alive -= 1,
+        }
+        drop(state);
+        self.inner.returned.notify_one();
+    }
+}
+
+// How long to wait for playback to stop when resetting an instance.
+const RESET_TIMEOUT: Duration = Duration::from_secs(2);
+
+fn reset(idle: &mut Idle) -> Result<()> {
+    let mpv = &mut idle.mpv;
+    mpv.execute(&Stop::default())?;
+    for (name, value) in &idle.defaults {
+        mpv.set_property(name, &value[..])?;
+    }
+
+    // Drain the events of the last job, so the next one starts with an empty
+    // queue.
+    let deadline = Instant::now() + RESET_TIMEOUT;
+    loop {
+        if Instant::now() >= deadline {
+            return Err(Error::Timeout);
+        }
+        if mpv.wait_event(0.).is_some() {
+            continue;
+        }
+        if mpv.get_property::<bool>("idle-active")? {
+            return Ok(());
+        }
+        mpv.wait_event(0.05);
+    }
+}
+
+/// An instance taken from an [`MpvPool`]. It is reset and returned to the pool
+/// when dropped: playback is stopped, the playlist cleared, events drained and
+/// the [`restore`](PoolOptions::restore) properties set back. If resetting
+/// fails, the instance is discarded and replaced later.
+///
+/// Observed properties, enabled events and hooks are not reset. Use
+/// [discard](PooledMpv::discard) after changing them, as a hook left on a
+/// pooled instance would block its next user.
+pub struct PooledMpv {
+    idle: Option<Idle>,
+    pool: MpvPool,
+    discard: bool,
+}
+
+impl PooledMpv {
+    /// Destroy the instance instead of returning it to the pool.
+    pub fn discard(&mut self) {
+        self.discard = true;
+    }
+}
+
+impl Deref for PooledMpv {
+    type Target = Mpv;
+
+    fn deref(&self) -> &Mpv {
+        &self.idle.as_ref().unwrap().mpv
+    }
+}
+
+impl DerefMut for PooledMpv {
+    fn deref_mut(&mut self) -> &mut Mpv {
+        &mut self.idle.as_mut().unwrap().mpv
+    }
+}
+
+impl Drop for PooledMpv {
+    fn drop(&mut self) {
+        let idle = self
+            .idle
+            .take()
+            .filter(|_| !self.discard)
+            .and_then(|mut idle| reset(&mut idle).ok().map(|_| idle));
+        self.pool.put_back(idle);
+    }
+}
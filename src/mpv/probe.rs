@@ -0,0 +1,187 @@
+//! Reading the properties of a media file without playing it.
+
+use crate::chapters::{Chapter, Edition};
+use crate::commands::LoadFile;
+use crate::events::Event;
+use crate::metadata::{Metadata, MetadataSource};
+use crate::tracks::{Track, TrackType};
+use crate::{Error, Mpv, Result, mpv_error};
+
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// A summary of a media file, returned by [`probe`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaInfo {
+    pub path: String,
+    /// Duration in seconds, if known.
+    pub duration: Option<f64>,
+    /// Symbolic name of the container format, e.g. `mkv`.
+    pub container: Option<String>,
+    /// Size of the file in bytes.
+    pub file_size: Option<i64>,
+    pub tracks: Vec<Track>,
+    pub chapters: Vec<Chapter>,
+    pub editions: Vec<Edition>,
+    /// The file's tags.
+    pub metadata: Metadata,
+}
+
+impl MediaInfo {
+    /// The tracks of the given type.
+    pub fn tracks_of(&self, kind: TrackType) -> impl Iterator<Item = &Track> {
+        self.tracks.iter().filter(move |track| track.kind == kind)
+    }
+
+    /// The main video track: the one mpv selected, or else the first one that
+    /// isn't cover art.
+    pub fn video(&self) -> Option<&Track> {
+        self.main_track(TrackType::Video)
+    }
+
+    /// The main audio track: the one mpv selected, or else the first one.
+    pub fn audio(&self) -> Option<&Track> {
+        self.main_track(TrackType::Audio)
+    }
+
+    fn main_track(&self, kind: TrackType) -> Option<&Track> {
+        self.tracks_of(kind)
+            .find(|track| track.selected)
+            .or_else(|| self.tracks_of(kind).find(|track| !track.albumart))
+    }
+
+    /// Width and height of the main video track.
+    pub fn resolution(&self) -> Option<(i64, i64)> {
+        let video = self.video()?;
+        Some((video.demux_width?, video.demux_height?))
+    }
+
+    /// Frame rate of the main video track, as stored in the container.
+    pub fn frame_rate(&self) -> Option<f64> {
+        self.video()?.demux_fps
+    }
+
+    /// Channel count and sample rate of the main audio track.
+    pub fn audio_format(&self) -> Option<(i64, i64)> {
+        let audio = self.audio()?;
+        Some((audio.demux_channel_count?, audio.demux_samplerate?))
+    }
+}
+
+/// Options for [`probe_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeOptions {
+    /// How long to wait for the file to be opened. Defaults to 10 seconds.
+    pub timeout: Duration,
+    /// Additional options for the `Mpv` instance, e.g. demuxer options.
+    pub options: Vec<(String, String)>,
+}
+
+impl Default for ProbeOptions {
+    fn default() -> ProbeOptions {
+        ProbeOptions {
+            timeout: Duration::from_secs(10),
+            options: Vec::new(),
+        }
+    }
+}
+
+impl ProbeOptions {
+    pub fn timeout(mut self, timeout: Duration) -> ProbeOptions {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn option<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> ProbeOptions {
+        self.options.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// Probe `path` with the default [`ProbeOptions`].
+pub fn probe<P: AsRef<Path>>(path: P) -> Result<MediaInfo> {
+    probe_with(path, &ProbeOptions::default())
+}
+
+/// Open `path` in a headless, paused `Mpv` instance and read its properties
+/// once it is loaded.
+///
+/// Returns `Error::Loadfile` if mpv can't play the file, and `Error::Timeout`
+/// if it wasn't loaded within the timeout.
+pub fn probe_with<P: AsRef<Path>>(path: P, options: &ProbeOptions) -> Result<MediaInfo> {
+    let mut mpv = Mpv::with_initializer(|init| {
+        for (name, value) in [
+            ("config", "no"),
+            ("load-scripts", "no"),
+            ("ytdl", "no"),
+            ("vo", "null"),
+            ("ao", "null"),
+        ] {
+            init.set_property(name, value)?;
+        }
+        for (name, value) in &options.options {
+            init.set_property(name, &value[..])?;
+        }
+        Ok(())
+    })?;
+    probe_on(&mut mpv, path, options.timeout)
+}
+
+/// Probe `path` on an existing instance, e.g. one from an
+/// [`MpvPool`](crate::pool::MpvPool). The instance should use the `null` video
+/// and audio outputs. It is paused, and the file stays loaded afterwards.
+pub fn probe_on<P: AsRef<Path>>(mpv: &mut Mpv, path: P, timeout: Duration) -> Result<MediaInfo> {
+    let path = path.as_ref();
+    let path = path
+        .to_str()
+        .ok_or(Error::Raw(mpv_error::InvalidParameter))?;
+
+    mpv.set_property("pause", true)?;
+    // Replacing a file still loaded from an earlier probe ends it first, so
+    // only the end of the new entry counts.
+    let entry_id = mpv.execute(&LoadFile::new(path))?;
+
+    let loading_failed = |error| Error::Loadfile {
+        error: Rc::new(error),
+    };
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(Error::Timeout);
+        }
+        match mpv.wait_event(remaining.as_secs_f64()) {
+            Some(Ok(Event::FileLoaded)) => break,
+            // Failed to load, or ended without an error code, e.g. because
+            // there is nothing to play in the file.
+            Some(Ok(Event::EndFile {
+                playlist_entry_id,
+                error,
+                ..
+            })) if entry_id.is_none_or(|id| id == playlist_entry_id) => {
+                return Err(loading_failed(
+                    error.unwrap_or(Error::Raw(mpv_error::NothingToPlay)),
+                ));
+            }
+            Some(Ok(Event::Shutdown)) => {
+                return Err(loading_failed(Error::Raw(mpv_error::NothingToPlay)));
+            }
+            // Load failures come with `EndFile`, other errors are about events
+            // unrelated to this file.
+            Some(Ok(_)) | Some(Err(_)) | None => {}
+        }
+    }
+
+    let chapters = mpv.chapters();
+    Ok(MediaInfo {
+        path: path.to_owned(),
+        duration: mpv.get_property("duration").ok(),
+        container: mpv.get_property("file-format").ok(),
+        file_size: mpv.get_property("file-size").ok(),
+        tracks: mpv.tracks().list()?,
+        chapters: chapters.list()?,
+        editions: chapters.editions()?,
+        metadata: mpv.metadata(&MetadataSource::File).unwrap_or_default(),
+    })
+}
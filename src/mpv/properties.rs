@@ -0,0 +1,247 @@
+//! Typed descriptors for mpv properties.
+//!
+//! A [`Property<T>`] ties a property name to the Rust type it is read, written
+//! and observed as, so e.g. reading `volume` as an `i64` is a compile error:
+//!
+//! ```
+//! # use libmpv2::{Mpv, properties};
+//! # fn main() -> libmpv2::Result<()> {
+//! let mpv = Mpv::new()?;
+//! mpv.set(properties::VOLUME, 50.)?;
+//! let volume: f64 = mpv.get(properties::VOLUME)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::events::{Event, PropertyData};
+use crate::mpv_node::MpvNode;
+use crate::{GetData, Mpv, Result, SetData};
+
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Types that can be decoded from the data of a `PropertyChange` or
+/// `GetPropertyReply` event.
+pub trait FromPropertyData: Sized {
+    fn from_property_data(data: &PropertyData<'_>) -> Option<Self>;
+}
+
+impl FromPropertyData for bool {
+    fn from_property_data(data: &PropertyData<'_>) -> Option<bool> {
+        match *data {
+            PropertyData::Flag(flag) => Some(flag),
+            _ => None,
+        }
+    }
+}
+
+impl FromPropertyData for i64 {
+    fn from_property_data(data: &PropertyData<'_>) -> Option<i64> {
+        match *data {
+            PropertyData::Int64(int) => Some(int),
+            _ => None,
+        }
+    }
+}
+
+impl FromPropertyData for f64 {
+    fn from_property_data(data: &PropertyData<'_>) -> Option<f64> {
+        match *data {
+            PropertyData::Double(double) => Some(double),
+            PropertyData::Int64(int) => Some(int as f64),
+            _ => None,
+        }
+    }
+}
+
+impl FromPropertyData for String {
+    fn from_property_data(data: &PropertyData<'_>) -> Option<String> {
+        match *data {
+            PropertyData::Str(s) | PropertyData::OsdStr(s) => Some(s.to_owned()),
+            _ => None,
+        }
+    }
+}
+
+impl FromPropertyData for MpvNode {
+    fn from_property_data(data: &PropertyData<'_>) -> Option<MpvNode> {
+        match data {
+            PropertyData::Node(node) => Some(node.clone()),
+            PropertyData::Str(s) | PropertyData::OsdStr(s) => Some(MpvNode::from(*s)),
+            PropertyData::Flag(flag) => Some(MpvNode::Flag(*flag)),
+            PropertyData::Int64(int) => Some(MpvNode::Int64(*int)),
+            PropertyData::Double(double) => Some(MpvNode::Double(*double)),
+        }
+    }
+}
+
+/// The name of an mpv property together with the type of its value.
+pub struct Property<T> {
+    name: &'static str,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T> Property<T> {
+    /// Describe the property `name` with values of type `T`. Use this for
+    /// properties without a constant in this module.
+    pub const fn new(name: &'static str) -> Property<T> {
+        Property {
+            name,
+            _type: PhantomData,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<T: FromPropertyData> Property<T> {
+    /// Decode `data` as a value of this property.
+    pub fn decode(&self, data: &PropertyData<'_>) -> Option<T> {
+        T::from_property_data(data)
+    }
+
+    /// Decode the value carried by `event` if it is a `PropertyChange` or
+    /// `GetPropertyReply` for this property.
+    pub fn parse(&self, event: &Event<'_>) -> Option<T> {
+        match event {
+            Event::PropertyChange { name, change, .. } if *name == self.name => self.decode(change),
+            Event::GetPropertyReply { name, result, .. } if *name == self.name => {
+                self.decode(result)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<T> Clone for Property<T> {
+    fn clone(&self) -> Property<T> {
+        *self
+    }
+}
+
+impl<T> Copy for Property<T> {}
+
+impl<T> fmt::Debug for Property<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Property").field(&self.name).finish()
+    }
+}
+
+impl Mpv {
+    /// Read the value of a typed property. See [get_property](Mpv::get_property).
+    pub fn get<T: GetData>(&self, property: Property<T>) -> Result<T> {
+        self.get_property(property.name)
+    }
+
+    /// Set a typed property. See [set_property](Mpv::set_property).
+    pub fn set<T: SetData>(&self, property: Property<T>, value: T) -> Result<()> {
+        self.set_property(property.name, value)
+    }
+
+    /// Observe a typed property in the format matching its type. Decode the
+    /// resulting `PropertyChange` events with [Property::parse]. See
+    /// [observe_property](Mpv::observe_property).
+    pub fn observe<T: GetData>(&self, property: Property<T>, id: u64) -> Result<()> {
+        self.observe_property(property.name, T::get_format(), id)
+    }
+}
+
+macro_rules! properties {
+    ($($(#[$attr:meta])* $konst:ident: $ty:ty = $name:literal;)*) => {
+        $(
+            $(#[$attr])*
+            pub const $konst: Property<$ty> = Property::new($name);
+        )*
+    };
+}
+
+properties! {
+    /// Whether playback is paused.
+    PAUSE: bool = "pause";
+    /// Position in the current file in seconds.
+    TIME_POS: f64 = "time-pos";
+    /// Remaining length of the file in seconds.
+    TIME_REMAINING: f64 = "time-remaining";
+    /// Position in the current file in seconds, clamped to the file's duration.
+    PLAYBACK_TIME: f64 = "playback-time";
+    /// Position in the current file in percent.
+    PERCENT_POS: f64 = "percent-pos";
+    /// Duration of the current file in seconds.
+    DURATION: f64 = "duration";
+    /// Full path of the current file.
+    PATH: String = "path";
+    /// The file name of the current file, without its directory.
+    FILENAME: String = "filename";
+    /// The title of the current file, or its file name if it has none.
+    MEDIA_TITLE: String = "media-title";
+    /// Symbolic name of the container format.
+    FILE_FORMAT: String = "file-format";
+    /// Size of the current file in bytes.
+    FILE_SIZE: i64 = "file-size";
+    /// Volume in percent, 100 being unchanged.
+    VOLUME: f64 = "volume";
+    /// Whether audio is muted.
+    MUTE: bool = "mute";
+    /// Playback speed factor.
+    SPEED: f64 = "speed";
+    /// Whether no file is loaded.
+    IDLE_ACTIVE: bool = "idle-active";
+    /// Whether playback is stopped, whatever the reason.
+    CORE_IDLE: bool = "core-idle";
+    /// Whether playback reached the end of the file (with `keep-open`).
+    EOF_REACHED: bool = "eof-reached";
+    /// Whether the player is seeking.
+    SEEKING: bool = "seeking";
+    /// Whether playback is paused because the cache is filling up.
+    PAUSED_FOR_CACHE: bool = "paused-for-cache";
+    /// How full the cache is while buffering, in percent.
+    CACHE_BUFFERING_STATE: i64 = "cache-buffering-state";
+    /// Zero based index of the current playlist entry.
+    PLAYLIST_POS: i64 = "playlist-pos";
+    /// Number of playlist entries.
+    PLAYLIST_COUNT: i64 = "playlist-count";
+    /// Zero based index of the current chapter.
+    CHAPTER: i64 = "chapter";
+    /// Number of chapters.
+    CHAPTERS: i64 = "chapters";
+    /// Id of the current edition.
+    CURRENT_EDITION: i64 = "current-edition";
+    /// Number of editions.
+    EDITIONS: i64 = "editions";
+    /// Selected audio track: a track id, `no` or `auto`.
+    AID: String = "aid";
+    /// Selected video track: a track id, `no` or `auto`.
+    VID: String = "vid";
+    /// Selected subtitle track: a track id, `no` or `auto`.
+    SID: String = "sid";
+    /// Selected secondary subtitle track: a track id, `no` or `auto`.
+    SECONDARY_SID: String = "secondary-sid";
+    /// Width of the decoded video.
+    WIDTH: i64 = "width";
+    /// Height of the decoded video.
+    HEIGHT: i64 = "height";
+    /// Frame rate as stored in the container.
+    CONTAINER_FPS: f64 = "container-fps";
+    /// Description of the video decoder.
+    VIDEO_CODEC: String = "video-codec";
+    /// Description of the audio decoder.
+    AUDIO_CODEC: String = "audio-codec";
+    /// Audio delay in seconds.
+    AUDIO_DELAY: f64 = "audio-delay";
+    /// Subtitle delay in seconds.
+    SUB_DELAY: f64 = "sub-delay";
+    /// Whether subtitles are shown.
+    SUB_VISIBILITY: bool = "sub-visibility";
+    /// Whether the video window is fullscreen.
+    FULLSCREEN: bool = "fullscreen";
+    /// Seconds of demuxed data ahead of the playback position.
+    DEMUXER_CACHE_DURATION: f64 = "demuxer-cache-duration";
+    /// The selected video output driver list.
+    VO: String = "vo";
+    /// The selected audio output driver list.
+    AO: String = "ao";
+    /// Version string of the mpv core.
+    MPV_VERSION: String = "mpv-version";
+}
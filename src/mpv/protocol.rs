@@ -2,7 +2,6 @@ use super::*;
 use std::alloc::{self, Layout};
 use std::mem;
 use std::os::raw as ctype;
-use std::panic;
 use std::panic::RefUnwindSafe;
 use std::slice;
 
@@ -54,7 +53,7 @@ where
         (*stream_cb_info).close_fn = Some(close_wrapper::<T, U>);
     }
 
-    let ret = panic::catch_unwind(|| unsafe {
+    callback::catch(Callback::Protocol, mpv_error::Generic as _, || unsafe {
         let uri = mpv_cstr_to_str!(uri as *const _).unwrap();
 
         let protocol_data = &mut *(user_data as *mut InitProtocolData<T, U>);
@@ -64,13 +63,8 @@ where
             (*protocol_data_raw).cookie,
             ((*protocol_data_raw).open_fn)(&mut (*protocol_data).user_data, uri),
         );
-    });
-
-    if ret.is_ok() {
         0
-    } else {
-        mpv_error::Generic as _
-    }
+    })
 }
 
 unsafe extern "C" fn read_wrapper<T, U>(
@@ -84,11 +78,10 @@ where
 {
     let data = wrapper_cookie as *mut ProtocolData<T, U>;
 
-    let ret = panic::catch_unwind(|| unsafe {
+    callback::catch(Callback::Protocol, -1, || unsafe {
         let slice = slice::from_raw_parts_mut(buf, nbytes as _);
         ((*data).read_fn)(&mut *(*data).cookie, slice)
-    });
-    if let Ok(ret) = ret { ret } else { -1 }
+    })
 }
 
 unsafe extern "C" fn seek_wrapper<T, U>(wrapper_cookie: *mut ctype::c_void, offset: i64) -> i64
@@ -102,14 +95,9 @@ where
         return mpv_error::Unsupported as _;
     }
 
-    let ret = panic::catch_unwind(|| unsafe {
+    callback::catch(Callback::Protocol, mpv_error::Generic as _, || unsafe {
         (*(*data).seek_fn.as_ref().unwrap())(&mut *(*data).cookie, offset)
-    });
-    if let Ok(ret) = ret {
-        ret
-    } else {
-        mpv_error::Generic as _
-    }
+    })
 }
 
 unsafe extern "C" fn size_wrapper<T, U>(wrapper_cookie: *mut ctype::c_void) -> i64
@@ -123,17 +111,11 @@ where
         return mpv_error::Unsupported as _;
     }
 
-    let ret = panic::catch_unwind(|| unsafe {
+    callback::catch(Callback::Protocol, mpv_error::Unsupported as _, || unsafe {
         (*(*data).size_fn.as_ref().unwrap())(&mut *(*data).cookie)
-    });
-    if let Ok(ret) = ret {
-        ret
-    } else {
-        mpv_error::Unsupported as _
-    }
+    })
 }
 
-#[allow(unused_must_use)]
 extern "C" fn close_wrapper<T, U>(wrapper_cookie: *mut ctype::c_void)
 where
     T: RefUnwindSafe,
@@ -143,7 +125,9 @@ where
     let data = unsafe { Box::from_raw(wrapper_cookie as *mut ProtocolData<T, U>) };
 
     // Free cookie memory
-    panic::catch_unwind(|| unsafe { ((*data).close_fn)(Box::from_raw((*data).cookie)) });
+    callback::catch(Callback::Protocol, (), || unsafe {
+        ((*data).close_fn)(Box::from_raw((*data).cookie))
+    });
 }
 
 struct InitProtocolData<T, U> {
@@ -190,6 +174,8 @@ impl<'parent, T: RefUnwindSafe, U: RefUnwindSafe> Protocol<'parent, T, U> {
     /// # Safety
     /// Do not call libmpv functions in any supplied function.
     /// All panics of the provided functions are catched and can be used as generic error returns.
+    /// They are also passed to the handler set with
+    /// [set_callback_error_handler](crate::set_callback_error_handler).
     pub unsafe fn new(
         mpv: &'parent Mpv,
         name: String,
@@ -219,6 +205,7 @@ impl<'parent, T: RefUnwindSafe, U: RefUnwindSafe> Protocol<'parent, T, U> {
     /// Will return `Err` if a `Protocol` with the same name is already
     /// registered
     pub fn register(&self) -> Result<()> {
+        callback::assert_outside("Protocol::register");
         let name = CString::new(&self.name[..])?;
         unsafe {
             mpv_err(
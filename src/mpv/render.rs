@@ -1,6 +1,9 @@
-use crate::{Error, Result, mpv::mpv_err};
+use crate::{
+    Callback, Error, Result,
+    mpv::{callback, mpv_err},
+};
 use std::collections::HashMap;
-use std::ffi::{CStr, c_char, c_void};
+use std::ffi::{CStr, CString, c_char, c_void};
 use std::os::raw::c_int;
 use std::ptr;
 
@@ -79,6 +82,9 @@ pub mod mpv_render_update {
 
 pub enum RenderParamApiType {
     OpenGl,
+    /// The software renderer, which renders into memory surfaces. See
+    /// [render_sw](RenderContext::render_sw).
+    Software,
 }
 
 pub enum RenderParam<GLContext> {
@@ -96,6 +102,14 @@ pub enum RenderParam<GLContext> {
     NextFrameInfo(RenderFrameInfo),
     BlockForTargetTime(bool),
     SkipRendering(bool),
+    /// Software renderer only: target surface size as `[width, height]`.
+    SwSize([i32; 2]),
+    /// Software renderer only: target surface pixel format, e.g. `"rgb0"`.
+    SwFormat(String),
+    /// Software renderer only: target surface bytes per line.
+    SwStride(usize),
+    /// Software renderer only: pointer to the first pixel of the target surface.
+    SwPointer(*mut c_void),
 }
 
 impl<C> From<&RenderParam<C>> for u32 {
@@ -115,37 +129,55 @@ impl<C> From<&RenderParam<C>> for u32 {
             RenderParam::NextFrameInfo(_) => 11,
             RenderParam::BlockForTargetTime(_) => 12,
             RenderParam::SkipRendering(_) => 13,
+            RenderParam::SwSize(_) => 17,
+            RenderParam::SwFormat(_) => 18,
+            RenderParam::SwStride(_) => 19,
+            RenderParam::SwPointer(_) => 20,
         }
     }
 }
 
 unsafe extern "C" fn gpa_wrapper<GLContext>(ctx: *mut c_void, name: *const c_char) -> *mut c_void {
     if ctx.is_null() {
-        panic!("ctx for get_proc_address wrapper is NULL");
+        callback::report(
+            Callback::GetProcAddress,
+            String::from("ctx for get_proc_address wrapper is NULL"),
+        );
+        return ptr::null_mut();
     }
 
     let params: *mut OpenGLInitParams<GLContext> = ctx as _;
     let params = unsafe { &*params };
-    (params.get_proc_address)(
-        &params.ctx,
-        unsafe { CStr::from_ptr(name) }
-            .to_str()
-            .expect("Could not convert function name to str"),
-    )
+    let Ok(name) = unsafe { CStr::from_ptr(name) }.to_str() else {
+        callback::report(
+            Callback::GetProcAddress,
+            String::from("Could not convert function name to str"),
+        );
+        return ptr::null_mut();
+    };
+    callback::catch(Callback::GetProcAddress, ptr::null_mut(), || {
+        (params.get_proc_address)(&params.ctx, name)
+    })
 }
 
 unsafe extern "C" fn ru_wrapper<F: Fn() + Send + 'static>(ctx: *mut c_void) {
     if ctx.is_null() {
-        panic!("ctx for render_update wrapper is NULL");
+        callback::report(
+            Callback::RenderUpdate,
+            String::from("ctx for render_update wrapper is NULL"),
+        );
+        return;
     }
 
-    unsafe { (*(ctx as *mut F))() };
+    callback::catch(Callback::RenderUpdate, (), || unsafe {
+        (*(ctx as *mut F))()
+    });
 }
 
 impl<C> From<OpenGLInitParams<C>> for libmpv2_sys::mpv_opengl_init_params {
     fn from(val: OpenGLInitParams<C>) -> Self {
         Self {
-            get_proc_address: Some(gpa_wrapper::<OpenGLInitParams<C>>),
+            get_proc_address: Some(gpa_wrapper::<C>),
             get_proc_address_ctx: Box::into_raw(Box::new(val)) as *mut c_void,
         }
     }
@@ -160,6 +192,9 @@ impl<C> From<RenderParam<C>> for libmpv2_sys::mpv_render_param {
                 RenderParamApiType::OpenGl => {
                     libmpv2_sys::MPV_RENDER_API_TYPE_OPENGL.as_ptr() as *mut c_void
                 }
+                RenderParamApiType::Software => {
+                    libmpv2_sys::MPV_RENDER_API_TYPE_SW.as_ptr() as *mut c_void
+                }
             },
             RenderParam::InitParams(params) => {
                 Box::into_raw(Box::new(libmpv2_sys::mpv_opengl_init_params::from(params)))
@@ -186,6 +221,12 @@ impl<C> From<RenderParam<C>> for libmpv2_sys::mpv_render_param {
             RenderParam::SkipRendering(skip_rendering) => {
                 Box::into_raw(Box::new(skip_rendering as c_int)) as *mut c_void
             }
+            RenderParam::SwSize(size) => Box::into_raw(Box::new(size)) as *mut c_void,
+            RenderParam::SwFormat(format) => CString::new(format)
+                .map(|format| format.into_raw() as *mut c_void)
+                .unwrap_or(ptr::null_mut()),
+            RenderParam::SwStride(stride) => Box::into_raw(Box::new(stride)) as *mut c_void,
+            RenderParam::SwPointer(ptr) => ptr,
         };
         Self { type_, data }
     }
@@ -195,6 +236,12 @@ unsafe fn free_void_data<T>(ptr: *mut c_void) {
     drop(unsafe { Box::<T>::from_raw(ptr as *mut T) });
 }
 
+unsafe fn free_cstring(ptr: *mut c_void) {
+    if !ptr.is_null() {
+        drop(unsafe { CString::from_raw(ptr as *mut c_char) });
+    }
+}
+
 unsafe fn free_init_params<C>(ptr: *mut c_void) {
     let params = unsafe { Box::from_raw(ptr as *mut libmpv2_sys::mpv_opengl_init_params) };
     drop(unsafe { Box::from_raw(params.get_proc_address_ctx as *mut OpenGLInitParams<C>) });
@@ -205,6 +252,7 @@ impl RenderContext {
         mpv: &mut libmpv2_sys::mpv_handle,
         params: impl IntoIterator<Item = RenderParam<C>>,
     ) -> Result<Self> {
+        callback::assert_outside("RenderContext::new");
         let params: Vec<_> = params.into_iter().collect();
         let mut raw_params: Vec<libmpv2_sys::mpv_render_param> = Vec::new();
         raw_params.reserve(params.len() + 1);
@@ -221,6 +269,9 @@ impl RenderContext {
                 RenderParam::ICCProfile(_) => Some(free_void_data::<Box<[u8]>>),
                 RenderParam::AmbientLight(_) => Some(free_void_data::<i32>),
                 RenderParam::NextFrameInfo(_) => Some(free_void_data::<RenderFrameInfo>),
+                RenderParam::SwSize(_) => Some(free_void_data::<[i32; 2]>),
+                RenderParam::SwFormat(_) => Some(free_cstring),
+                RenderParam::SwStride(_) => Some(free_void_data::<usize>),
                 _ => None,
             };
             let raw_param: libmpv2_sys::mpv_render_param = p.into();
@@ -257,6 +308,7 @@ impl RenderContext {
     }
 
     pub fn set_parameter<C>(&self, param: RenderParam<C>) -> Result<()> {
+        callback::assert_outside("RenderContext::set_parameter");
         unsafe {
             mpv_err(
                 (),
@@ -269,6 +321,7 @@ impl RenderContext {
     }
 
     pub fn get_info<C>(&self, param: RenderParam<C>) -> Result<RenderParam<C>> {
+        callback::assert_outside("RenderContext::get_info");
         let is_next_frame_info = matches!(param, RenderParam::NextFrameInfo(_));
         let raw_param = libmpv2_sys::mpv_render_param::from(param);
         let res = unsafe { libmpv2_sys::mpv_render_context_get_info(self.ctx, raw_param) };
@@ -322,6 +375,7 @@ impl RenderContext {
     ///            it uses a coordinate system with positive Y up, but videos use positive
     ///            Y down.
     pub fn render<GLContext>(&self, fbo: i32, width: i32, height: i32, flip: bool) -> Result<()> {
+        callback::assert_outside("RenderContext::render");
         let mut raw_params: Vec<libmpv2_sys::mpv_render_param> = Vec::with_capacity(3);
         let mut raw_ptrs: HashMap<*const c_void, DeleterFn> = HashMap::new();
 
@@ -360,6 +414,88 @@ impl RenderContext {
         ret
     }
 
+    /// Render video into a memory surface. Only valid for contexts created with
+    /// [RenderParamApiType::Software].
+    ///
+    /// The video frame is transformed as with other VOs: it gets scaled, and black
+    /// bars are added if the video size or aspect ratio mismatches with the target
+    /// size. Everything, including color conversion, scaling and OSD rendering is
+    /// done on the CPU, so this is slow.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The width of the target surface in pixels.
+    /// * `height` - The height of the target surface in pixels.
+    /// * `format` - The pixel format of the target surface, one of `"rgb0"`, `"bgr0"`,
+    ///   `"0bgr"`, `"0rgb"` or `"rgb24"`.
+    /// * `stride` - The number of bytes between the start of two lines. Must be a
+    ///   multiple of the pixel size.
+    /// * `buffer` - The target surface. Must hold at least `stride * height` bytes
+    ///   and be aligned to the pixel size.
+    ///
+    /// Will return `Err` if `format` contains a NUL byte, if `buffer` is too small
+    /// or misaligned for the given surface, or if mpv fails to render.
+    pub fn render_sw(
+        &self,
+        width: i32,
+        height: i32,
+        format: &str,
+        stride: usize,
+        buffer: &mut [u8],
+    ) -> Result<()> {
+        callback::assert_outside("RenderContext::render_sw");
+        let align = if format == "rgb24" { 1 } else { 4 };
+        if format.contains('\0')
+            || width <= 0
+            || height <= 0
+            || !stride.is_multiple_of(align)
+            || stride
+                .checked_mul(height as usize)
+                .is_none_or(|size| buffer.len() < size)
+            || !(buffer.as_ptr() as usize).is_multiple_of(align)
+        {
+            return Err(Error::Raw(
+                libmpv2_sys::mpv_error_MPV_ERROR_INVALID_PARAMETER,
+            ));
+        }
+
+        let mut raw_params: Vec<libmpv2_sys::mpv_render_param> = Vec::with_capacity(5);
+        let mut raw_ptrs: HashMap<*const c_void, DeleterFn> = HashMap::new();
+
+        let raw_param: libmpv2_sys::mpv_render_param =
+            RenderParam::<()>::SwSize([width, height]).into();
+        raw_ptrs.insert(raw_param.data, free_void_data::<[i32; 2]>);
+        raw_params.push(raw_param);
+        let raw_param: libmpv2_sys::mpv_render_param =
+            RenderParam::<()>::SwFormat(format.to_owned()).into();
+        raw_ptrs.insert(raw_param.data, free_cstring);
+        raw_params.push(raw_param);
+        let raw_param: libmpv2_sys::mpv_render_param = RenderParam::<()>::SwStride(stride).into();
+        raw_ptrs.insert(raw_param.data, free_void_data::<usize>);
+        raw_params.push(raw_param);
+        raw_params.push(RenderParam::<()>::SwPointer(buffer.as_mut_ptr() as *mut c_void).into());
+        // the raw array must end with type = 0
+        raw_params.push(libmpv2_sys::mpv_render_param {
+            type_: 0,
+            data: ptr::null_mut(),
+        });
+
+        let ret = unsafe {
+            mpv_err(
+                (),
+                libmpv2_sys::mpv_render_context_render(self.ctx, raw_params.as_mut_ptr()),
+            )
+        };
+
+        unsafe {
+            for (ptr, deleter) in raw_ptrs.iter() {
+                (deleter)(*ptr as _);
+            }
+        }
+
+        ret
+    }
+
     /// Tell the renderer that a frame was flipped at the given time. This is
     /// optional, but can help the player to achieve better timing.
     ///
@@ -368,6 +504,7 @@ impl RenderContext {
     ///
     /// If this is called while no video is initialized, it is ignored.
     pub fn report_swap(&self) {
+        callback::assert_outside("RenderContext::report_swap");
         unsafe { libmpv2_sys::mpv_render_context_report_swap(self.ctx) }
     }
 
@@ -375,12 +512,17 @@ impl RenderContext {
     /// configuration somehow changed and requires a redraw. Similar to [EventContext::set_wakeup_callback](crate::events::EventContext::set_wakeup_callback), you
     /// must not call any mpv API from the callback, and all the other listed restrictions apply (such
     /// as not exiting the callback by throwing exceptions).
+    /// Panics are caught and passed to the handler set with
+    /// [set_callback_error_handler](crate::set_callback_error_handler).
+    /// In debug builds, calling any `Mpv` or `RenderContext` method from the
+    /// callback panics instead of deadlocking.
     ///
     /// This can be called from any thread, except from an update callback. In case of the OpenGL backend,
     /// no OpenGL state or API is accessed.
     ///
     /// Calling this will raise an update callback immediately.
     pub fn set_update_callback<F: Fn() + Send + 'static>(&mut self, callback: F) {
+        callback::assert_outside("RenderContext::set_update_callback");
         if let Some(update_callback_cleanup) = self.update_callback_cleanup.take() {
             update_callback_cleanup();
         }
@@ -421,6 +563,7 @@ impl RenderContext {
     /// to the API user are set, or if the return value is 0, nothing needs
     /// to be done.
     pub fn update(&self) -> Result<MpvRenderUpdate> {
+        callback::assert_outside("RenderContext::update");
         let res = unsafe { libmpv2_sys::mpv_render_context_update(self.ctx) };
         match res.try_into() {
             Ok(res) => Ok(res),
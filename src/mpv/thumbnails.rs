@@ -0,0 +1,428 @@
+//! Thumbnails for seek bar previews.
+//!
+//! A [`ThumbnailGenerator`] opens a file in a headless instance, seeks to evenly
+//! spaced positions and renders each frame with the software renderer. The
+//! thumbnails can be packed into a [`SpriteSheet`] with a WebVTT index, the
+//! format most web players use for hover previews:
+//!
+//! ```no_run
+//! # use libmpv2::thumbnails::{SpriteSheet, ThumbnailGenerator};
+//! # fn main() -> libmpv2::Result<()> {
+//! let generator = ThumbnailGenerator::new(20, 160).cache_dir("/tmp/thumbnails");
+//! let thumbnails = generator.generate("video.mkv")?;
+//! let sheet = SpriteSheet::new(&thumbnails, 5);
+//! sheet.image.write_png("sprites.png")?;
+//! std::fs::write("sprites.vtt", sheet.webvtt("sprites.png"))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::capture::{CaptureSink, FrameCapture};
+use crate::commands::{LoadFile, Seek, SeekPrecision};
+use crate::events::Event;
+use crate::frame::{Fnv1a, Frame, PixelFormat};
+use crate::tracks::TrackType;
+use crate::{Error, Mpv, Result, mpv_error};
+
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{Read, Seek as _, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Where thumbnails are taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Spacing {
+    /// Exactly at evenly spaced positions.
+    #[default]
+    Even,
+    /// At the keyframe before each evenly spaced position. Much faster, as
+    /// no frames have to be decoded between the keyframe and the position.
+    Keyframes,
+}
+
+/// A frame of the video and its position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thumbnail {
+    /// Position of the frame in seconds.
+    pub time: f64,
+    pub frame: Frame,
+}
+
+/// The thumbnails of a file, returned by [`ThumbnailGenerator::generate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thumbnails {
+    /// Duration of the file in seconds.
+    pub duration: f64,
+    /// The thumbnails, ordered by time.
+    pub thumbnails: Vec<Thumbnail>,
+}
+
+/// Generates thumbnails of video files. See the [module docs](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThumbnailGenerator {
+    count: usize,
+    width: u32,
+    height: Option<u32>,
+    spacing: Spacing,
+    cache_dir: Option<PathBuf>,
+    black_threshold: Option<f64>,
+    timeout: Duration,
+}
+
+// How many times to step past a black frame, and by which fraction of the
+// distance to the next thumbnail.
+const BLACK_RETRIES: u32 = 4;
+const BLACK_STEP: f64 = 0.2;
+
+// How long to wait for the renderer to receive a frame after a seek.
+const FRAME_TIMEOUT: Duration = Duration::from_millis(500);
+
+// How much of the start and end of a file are hashed for the cache key.
+const HASH_BYTES: u64 = 1 << 20;
+
+impl ThumbnailGenerator {
+    /// Generate `count` thumbnails `width` pixels wide. The height follows the
+    /// aspect ratio of the video unless set with
+    /// [height](ThumbnailGenerator::height).
+    pub fn new(count: usize, width: u32) -> ThumbnailGenerator {
+        ThumbnailGenerator {
+            count,
+            width,
+            height: None,
+            spacing: Spacing::default(),
+            cache_dir: None,
+            black_threshold: Some(16.),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Use a fixed height. The video is letterboxed if its aspect ratio
+    /// differs.
+    pub fn height(mut self, height: u32) -> ThumbnailGenerator {
+        self.height = Some(height);
+        self
+    }
+
+    pub fn spacing(mut self, spacing: Spacing) -> ThumbnailGenerator {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Cache generated thumbnails in `dir`, keyed by a hash of the file's size,
+    /// its first and last megabyte, and the generator settings. Only local
+    /// files are cached.
+    pub fn cache_dir<P: Into<PathBuf>>(mut self, dir: P) -> ThumbnailGenerator {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Skip frames with a [mean luma](Frame::mean_luma) below `threshold` by
+    /// stepping forward, e.g. past fades to black. `None` disables it. Defaults
+    /// to 16.
+    pub fn skip_black(mut self, threshold: Option<f64>) -> ThumbnailGenerator {
+        self.black_threshold = threshold;
+        self
+    }
+
+    /// How long to wait for the file to open and for each seek. Defaults to
+    /// 10 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> ThumbnailGenerator {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Generate the thumbnails of `path`, or read them from the cache.
+    ///
+    /// Returns `Error::Loadfile` if the file can't be played or has no video,
+    /// and `Error::Timeout` if opening or seeking takes too long.
+    pub fn generate<P: AsRef<Path>>(&self, path: P) -> Result<Thumbnails> {
+        let path = path.as_ref();
+        let cache = match &self.cache_dir {
+            Some(dir) => self
+                .cache_key(path)
+                .map(|key| dir.join(format!("{:016x}", key))),
+            None => None,
+        };
+        if let Some(thumbnails) = cache.as_deref().and_then(|dir| read_cache(dir).ok()) {
+            return Ok(thumbnails);
+        }
+
+        let thumbnails = self.render(path)?;
+        if let Some(dir) = cache {
+            write_cache(&dir, &thumbnails)?;
+        }
+        Ok(thumbnails)
+    }
+
+    fn cache_key(&self, path: &Path) -> Option<u64> {
+        let mut file = File::open(path).ok()?;
+        let len = file.metadata().ok()?.len();
+
+        let mut hash = Fnv1a::new();
+        hash.write(&len.to_le_bytes());
+        let mut buf = Vec::new();
+        file.by_ref().take(HASH_BYTES).read_to_end(&mut buf).ok()?;
+        if len > HASH_BYTES {
+            file.seek(SeekFrom::Start(
+                len.saturating_sub(HASH_BYTES).max(HASH_BYTES),
+            ))
+            .ok()?;
+            file.read_to_end(&mut buf).ok()?;
+        }
+        hash.write(&buf);
+        hash.write(format!("{:?}", self.settings()).as_bytes());
+        Some(hash.finish())
+    }
+
+    // The settings that change the generated thumbnails.
+    fn settings(&self) -> (usize, u32, Option<u32>, Spacing, Option<u64>) {
+        let threshold = self.black_threshold.map(f64::to_bits);
+        (self.count, self.width, self.height, self.spacing, threshold)
+    }
+
+    fn render(&self, path: &Path) -> Result<Thumbnails> {
+        let path = path
+            .to_str()
+            .ok_or(Error::Raw(mpv_error::InvalidParameter))?;
+
+        let mut mpv = Mpv::with_initializer(|init| {
+            for (name, value) in [
+                ("config", "no"),
+                ("load-scripts", "no"),
+                ("ytdl", "no"),
+                ("ao", "null"),
+                ("aid", "no"),
+                ("sid", "no"),
+                ("pause", "yes"),
+                ("keep-open", "always"),
+                ("hr-seek-framedrop", "no"),
+                // Open the file without video first; the renderer can only be
+                // created once the size of the video is known.
+                ("vid", "no"),
+            ] {
+                init.set_property(name, value)?;
+            }
+            Ok(())
+        })?;
+        mpv.execute(&LoadFile::new(path))?;
+        self.wait(&mut mpv, |event| matches!(event, Event::FileLoaded))?;
+
+        let video = mpv
+            .tracks()
+            .of_type(TrackType::Video)?
+            .into_iter()
+            .find(|track| !track.albumart)
+            .ok_or(Error::Loadfile {
+                error: Rc::new(Error::Raw(mpv_error::NothingToPlay)),
+            })?;
+        let height = match (self.height, video.demux_width, video.demux_height) {
+            (Some(height), _, _) => height,
+            (None, Some(w), Some(h)) if w > 0 => {
+                ((self.width as u64 * h as u64 / w as u64) as u32).max(1)
+            }
+            (None, _, _) => self.width * 9 / 16,
+        };
+        let duration = mpv.get_property::<f64>("duration")?;
+
+        // Declared after `mpv`, so it is dropped first.
+        let mut capture = FrameCapture::new(&mpv, self.width, height, CaptureSink::Discard)?;
+        mpv.set_property("vid", video.id)?;
+
+        let precision = match self.spacing {
+            Spacing::Even => SeekPrecision::Exact,
+            Spacing::Keyframes => SeekPrecision::Keyframes,
+        };
+        let step = duration / self.count.max(1) as f64;
+        let mut thumbnails = Vec::with_capacity(self.count);
+        for i in 0..self.count {
+            // Centered within each interval, which avoids the first frame
+            // (often black) and the end of the file.
+            let mut target = step * (i as f64 + 0.5);
+            let mut tries = 0;
+            let thumbnail = loop {
+                mpv.execute(&Seek::absolute(target).precision(precision))?;
+                self.wait(&mut mpv, |event| matches!(event, Event::PlaybackRestart))?;
+                let deadline = Instant::now() + FRAME_TIMEOUT;
+                while capture.capture_update()?.is_none() && Instant::now() < deadline {
+                    thread::sleep(Duration::from_millis(5));
+                }
+                capture.capture()?;
+                let frame = capture.frame();
+
+                let black = self
+                    .black_threshold
+                    .is_some_and(|threshold| frame.mean_luma() < threshold);
+                if !black || tries == BLACK_RETRIES {
+                    break Thumbnail {
+                        time: mpv.get_property("time-pos").unwrap_or(target),
+                        frame: frame.clone(),
+                    };
+                }
+                tries += 1;
+                target += step * BLACK_STEP;
+            };
+            thumbnails.push(thumbnail);
+        }
+
+        Ok(Thumbnails {
+            duration,
+            thumbnails,
+        })
+    }
+
+    // Wait for an event matching `done`, failing if the file ends or the
+    // timeout expires.
+    fn wait<F: Fn(&Event<'_>) -> bool>(&self, mpv: &mut Mpv, done: F) -> Result<()> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(Error::Timeout);
+            }
+            match mpv.wait_event(remaining.as_secs_f64()) {
+                Some(Ok(event)) if done(&event) => return Ok(()),
+                Some(Ok(Event::EndFile { error, .. })) => {
+                    return Err(Error::Loadfile {
+                        error: Rc::new(error.unwrap_or(Error::Raw(mpv_error::NothingToPlay))),
+                    });
+                }
+                Some(Ok(Event::Shutdown)) => {
+                    return Err(Error::Loadfile {
+                        error: Rc::new(Error::Raw(mpv_error::NothingToPlay)),
+                    });
+                }
+                Some(Err(e)) => {
+                    return Err(Error::Loadfile { error: Rc::new(e) });
+                }
+                Some(Ok(_)) | None => {}
+            }
+        }
+    }
+}
+
+// A cache entry is a directory with one PPM image per thumbnail and an index
+// with the duration on the first line and the time of each thumbnail on the
+// following ones.
+fn read_cache(dir: &Path) -> Result<Thumbnails> {
+    let index = fs::read_to_string(dir.join("index"))?;
+    let mut lines = index.lines().map(|line| {
+        line.parse::<f64>()
+            .map_err(|_| Error::Conversion(String::from("invalid thumbnail cache index")))
+    });
+    let duration = lines.next().unwrap_or(Ok(0.))?;
+    let thumbnails = lines
+        .enumerate()
+        .map(|(i, time)| {
+            Ok(Thumbnail {
+                time: time?,
+                frame: Frame::decode_ppm(&fs::read(dir.join(format!("{:04}.ppm", i)))?)?,
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(Thumbnails {
+        duration,
+        thumbnails,
+    })
+}
+
+fn write_cache(dir: &Path, thumbnails: &Thumbnails) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let mut index = format!("{}\n", thumbnails.duration);
+    for (i, thumbnail) in thumbnails.thumbnails.iter().enumerate() {
+        fs::write(
+            dir.join(format!("{:04}.ppm", i)),
+            thumbnail.frame.encode_ppm(),
+        )?;
+        let _ = writeln!(index, "{}", thumbnail.time);
+    }
+    // Written last, so an interrupted write is not read back as complete.
+    fs::write(dir.join("index"), index)?;
+    Ok(())
+}
+
+/// The position of a thumbnail in a [`SpriteSheet`], and the part of the file
+/// it previews.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpriteTile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Start of the previewed interval in seconds.
+    pub start: f64,
+    /// End of the previewed interval in seconds.
+    pub end: f64,
+}
+
+/// Thumbnails packed into a single image, row by row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteSheet {
+    pub image: Frame,
+    pub tiles: Vec<SpriteTile>,
+}
+
+impl SpriteSheet {
+    /// Pack `thumbnails` into a grid `columns` wide. Each tile previews the file
+    /// from the time of its thumbnail until the next one; the first starts at 0
+    /// and the last ends at the duration.
+    pub fn new(thumbnails: &Thumbnails, columns: u32) -> SpriteSheet {
+        let list = &thumbnails.thumbnails;
+        let columns = columns.clamp(1, (list.len() as u32).max(1));
+        let rows = (list.len() as u32).div_ceil(columns);
+        let (width, height) = list
+            .first()
+            .map_or((0, 0), |t| (t.frame.width, t.frame.height));
+
+        let mut image = Frame::new(width * columns, height * rows, PixelFormat::Rgb24);
+        let mut tiles = Vec::with_capacity(list.len());
+        for (i, thumbnail) in list.iter().enumerate() {
+            let (x, y) = (i as u32 % columns * width, i as u32 / columns * height);
+            image.blit(&thumbnail.frame, x, y);
+            tiles.push(SpriteTile {
+                x,
+                y,
+                width,
+                height,
+                start: if i == 0 { 0. } else { thumbnail.time },
+                end: list
+                    .get(i + 1)
+                    .map_or(thumbnails.duration, |next| next.time),
+            });
+        }
+        SpriteSheet { image, tiles }
+    }
+
+    /// A WebVTT index of the tiles, referring to the sheet as `image_url` with
+    /// spatial media fragments (`#xywh=`).
+    pub fn webvtt(&self, image_url: &str) -> String {
+        let mut vtt = String::from("WEBVTT\n");
+        for tile in &self.tiles {
+            let _ = write!(
+                vtt,
+                "\n{} --> {}\n{}#xywh={},{},{},{}\n",
+                vtt_time(tile.start),
+                vtt_time(tile.end),
+                image_url,
+                tile.x,
+                tile.y,
+                tile.width,
+                tile.height,
+            );
+        }
+        vtt
+    }
+}
+
+fn vtt_time(seconds: f64) -> String {
+    let millis = (seconds.max(0.) * 1000.).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
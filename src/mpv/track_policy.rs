@@ -0,0 +1,282 @@
+//! Choosing tracks before playback starts.
+//!
+//! A [`TrackPolicy`] sees the decoded `track-list` of a file once it is
+//! loaded, and returns the tracks to select. [`TrackPolicyHook`] runs a policy
+//! from the `on_preloaded` hook, so the selection is in place before the first
+//! frame is decoded:
+//!
+//! ```no_run
+//! # use libmpv2::{Mpv, track_policy::*};
+//! # fn main() -> libmpv2::Result<()> {
+//! let mut mpv = Mpv::new()?;
+//! let policy = PreferencePolicy {
+//!     audio_languages: vec!["ja".into(), "en".into()],
+//!     sub_languages: vec!["en".into()],
+//!     subtitles: SubtitleMode::Always,
+//!     ..PreferencePolicy::default()
+//! };
+//! let hook = TrackPolicyHook::register(&mpv, policy, 1)?;
+//! mpv.command("loadfile", &["video.mkv"])?;
+//! while let Some(event) = mpv.wait_event(-1.) {
+//!     if let Some(id) = hook.matches(&event?) {
+//!         hook.run(&mpv, id)?;
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::events::Event;
+use crate::tracks::{Track, TrackSelection, TrackSlot, TrackType};
+use crate::{Mpv, Result};
+
+/// The tracks chosen by a [`TrackPolicy`]. `None` leaves mpv's own selection
+/// of that slot unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TrackChoice {
+    pub video: Option<TrackSelection>,
+    pub audio: Option<TrackSelection>,
+    pub sub: Option<TrackSelection>,
+    pub secondary_sub: Option<TrackSelection>,
+}
+
+impl TrackChoice {
+    fn slots(&self) -> [(TrackSlot, Option<TrackSelection>); 4] {
+        [
+            (TrackSlot::Video, self.video),
+            (TrackSlot::Audio, self.audio),
+            (TrackSlot::Sub, self.sub),
+            (TrackSlot::SecondarySub, self.secondary_sub),
+        ]
+    }
+
+    /// Select the chosen tracks on `mpv`.
+    pub fn apply(&self, mpv: &Mpv) -> Result<()> {
+        let tracks = mpv.tracks();
+        for (slot, selection) in self.slots() {
+            if let Some(selection) = selection {
+                tracks.select(slot, selection)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Picks the tracks of a file from its `track-list`.
+pub trait TrackPolicy {
+    fn choose(&self, tracks: &[Track]) -> TrackChoice;
+}
+
+impl<F: Fn(&[Track]) -> TrackChoice> TrackPolicy for F {
+    fn choose(&self, tracks: &[Track]) -> TrackChoice {
+        self(tracks)
+    }
+}
+
+/// When [`PreferencePolicy`] selects subtitles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SubtitleMode {
+    /// Leave subtitles to mpv.
+    #[default]
+    Auto,
+    /// Never show subtitles.
+    Off,
+    /// Only show forced subtitles, preferably in the language of the selected
+    /// audio track.
+    ForcedOnly,
+    /// Show subtitles whenever one in a preferred language exists.
+    Always,
+}
+
+/// Whether [`PreferencePolicy`] prefers tracks with some flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Preference {
+    Prefer,
+    Avoid,
+    #[default]
+    Indifferent,
+}
+
+impl Preference {
+    fn rank(&self, flag: bool) -> u8 {
+        match (self, flag) {
+            (Preference::Prefer, false) | (Preference::Avoid, true) => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// A [`TrackPolicy`] choosing tracks by preferences.
+///
+/// Candidates are ranked, in that order, by: commentary avoidance, position of
+/// their language in the language list, the hearing impaired preference, the
+/// position of their codec in `codecs`, and the container's default flag.
+/// Languages match exactly or as a prefix of a tag, so `en` matches `en-US`.
+/// An empty language list accepts all languages.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PreferencePolicy {
+    pub audio_languages: Vec<String>,
+    pub sub_languages: Vec<String>,
+    pub subtitles: SubtitleMode,
+    /// Preference for subtitles flagged for the hearing impaired.
+    pub hearing_impaired: Preference,
+    /// Avoid tracks with "commentary" in their title.
+    pub avoid_commentary: bool,
+    /// Preferred codecs, best first, e.g. `["truehd", "flac", "eac3"]`.
+    pub codecs: Vec<String>,
+}
+
+impl Default for PreferencePolicy {
+    fn default() -> PreferencePolicy {
+        PreferencePolicy {
+            audio_languages: Vec::new(),
+            sub_languages: Vec::new(),
+            subtitles: SubtitleMode::default(),
+            hearing_impaired: Preference::default(),
+            avoid_commentary: true,
+            codecs: Vec::new(),
+        }
+    }
+}
+
+fn language_rank(languages: &[String], track: &Track) -> Option<usize> {
+    if languages.is_empty() {
+        return Some(0);
+    }
+    let lang = track.lang.as_deref()?;
+    languages.iter().position(|pref| {
+        lang.eq_ignore_ascii_case(pref)
+            || (lang.len() > pref.len()
+                && lang.as_bytes()[pref.len()] == b'-'
+                && lang[..pref.len()].eq_ignore_ascii_case(pref))
+    })
+}
+
+fn is_commentary(track: &Track) -> bool {
+    track
+        .title
+        .as_deref()
+        .is_some_and(|title| title.to_ascii_lowercase().contains("commentary"))
+}
+
+impl PreferencePolicy {
+    fn codec_rank(&self, track: &Track) -> usize {
+        track
+            .codec
+            .as_deref()
+            .and_then(|codec| self.codecs.iter().position(|c| c == codec))
+            .unwrap_or(self.codecs.len())
+    }
+
+    // The best track of `kind` in one of `languages`, if any.
+    fn best<'a>(
+        &self,
+        tracks: &'a [Track],
+        kind: TrackType,
+        languages: &[String],
+        filter: impl Fn(&Track) -> bool,
+    ) -> Option<&'a Track> {
+        tracks
+            .iter()
+            .filter(|track| track.kind == kind && filter(track))
+            .filter_map(|track| Some((language_rank(languages, track)?, track)))
+            .min_by_key(|(lang, track)| {
+                (
+                    self.avoid_commentary && is_commentary(track),
+                    *lang,
+                    self.hearing_impaired.rank(track.hearing_impaired),
+                    self.codec_rank(track),
+                    !track.default,
+                    track.id,
+                )
+            })
+            .map(|(_, track)| track)
+    }
+}
+
+impl TrackPolicy for PreferencePolicy {
+    fn choose(&self, tracks: &[Track]) -> TrackChoice {
+        let video = self.best(tracks, TrackType::Video, &[], |track| !track.albumart);
+        let audio = self
+            .best(tracks, TrackType::Audio, &self.audio_languages, |_| true)
+            .or_else(|| self.best(tracks, TrackType::Audio, &[], |_| true));
+
+        let sub = match self.subtitles {
+            SubtitleMode::Auto => None,
+            SubtitleMode::Off => Some(TrackSelection::No),
+            SubtitleMode::ForcedOnly => {
+                let languages: Vec<_> = audio
+                    .and_then(|audio| audio.lang.clone())
+                    .into_iter()
+                    .chain(self.sub_languages.iter().cloned())
+                    .collect();
+                let forced = self
+                    .best(tracks, TrackType::Sub, &languages, |track| track.forced)
+                    .or_else(|| self.best(tracks, TrackType::Sub, &[], |track| track.forced));
+                Some(forced.map_or(TrackSelection::No, |track| TrackSelection::Id(track.id)))
+            }
+            SubtitleMode::Always => {
+                let sub = self.best(tracks, TrackType::Sub, &self.sub_languages, |_| true);
+                Some(sub.map_or(TrackSelection::No, |track| TrackSelection::Id(track.id)))
+            }
+        };
+
+        TrackChoice {
+            video: video.map(|track| TrackSelection::Id(track.id)),
+            audio: audio.map(|track| TrackSelection::Id(track.id)),
+            sub,
+            secondary_sub: None,
+        }
+    }
+}
+
+/// Runs a [`TrackPolicy`] from the `on_preloaded` hook, after the tracks of a
+/// file are known and before they are selected for playback.
+///
+/// The hook must be handled on the client that registered it: pass its events
+/// to [matches](TrackPolicyHook::matches) and call
+/// [run](TrackPolicyHook::run) with the returned id.
+pub struct TrackPolicyHook<P> {
+    policy: P,
+    reply_userdata: u64,
+}
+
+impl<P: TrackPolicy> TrackPolicyHook<P> {
+    /// Register the `on_preloaded` hook on `mpv`. Its events carry
+    /// `reply_userdata`.
+    pub fn register(mpv: &Mpv, policy: P, reply_userdata: u64) -> Result<TrackPolicyHook<P>> {
+        mpv.hook_add("on_preloaded", reply_userdata, 0)?;
+        Ok(TrackPolicyHook {
+            policy,
+            reply_userdata,
+        })
+    }
+
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    /// The hook id, if `event` is the invocation of this hook.
+    pub fn matches(&self, event: &Event<'_>) -> Option<u64> {
+        match *event {
+            Event::Hook {
+                name: "on_preloaded",
+                id,
+                reply_userdata,
+            } if reply_userdata == self.reply_userdata => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Apply the policy to the current file and continue the hook `id`. The
+    /// hook is continued even if choosing or selecting tracks failed.
+    pub fn run(&self, mpv: &Mpv, id: u64) -> Result<TrackChoice> {
+        let choice = mpv.tracks().list().and_then(|tracks| {
+            let choice = self.policy.choose(&tracks);
+            choice.apply(mpv)?;
+            Ok(choice)
+        });
+        mpv.hook_continue(id)?;
+        choice
+    }
+}
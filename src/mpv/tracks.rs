@@ -0,0 +1,279 @@
+//! Listing and selecting audio, video and subtitle tracks.
+
+use crate::commands::{TrackAdd, TrackReload, TrackRemove};
+use crate::mpv_node::MpvNode;
+use crate::{Error, Mpv, Result, mpv_error};
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The type of a track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrackType {
+    Video,
+    Audio,
+    Sub,
+}
+
+impl TrackType {
+    /// The name mpv uses for this type, as in `track-list` and command names
+    /// like `sub-add`.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            TrackType::Video => "video",
+            TrackType::Audio => "audio",
+            TrackType::Sub => "sub",
+        }
+    }
+}
+
+impl FromStr for TrackType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<TrackType> {
+        match s {
+            "video" => Ok(TrackType::Video),
+            "audio" => Ok(TrackType::Audio),
+            "sub" => Ok(TrackType::Sub),
+            _ => Err(Error::Conversion(format!("unknown track type `{}`", s))),
+        }
+    }
+}
+
+/// An entry of the `track-list` property.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    /// Id of the track, unique for its type. Used to select it.
+    pub id: i64,
+    pub kind: TrackType,
+    /// Id of the stream in the container, if known.
+    pub src_id: Option<i64>,
+    pub title: Option<String>,
+    pub lang: Option<String>,
+    /// Name of the codec, e.g. `h264`.
+    pub codec: Option<String>,
+    /// Human readable description of the decoder.
+    pub decoder_desc: Option<String>,
+    pub default: bool,
+    pub forced: bool,
+    /// Whether the track is flagged for the hearing impaired, e.g. SDH subtitles.
+    pub hearing_impaired: bool,
+    /// Whether the track is flagged for the visually impaired, e.g. audio
+    /// description.
+    pub visual_impaired: bool,
+    /// Whether the track is an image, e.g. cover art.
+    pub image: bool,
+    /// Whether the track is cover art embedded in the file or loaded from an
+    /// external image.
+    pub albumart: bool,
+    /// Whether the track was loaded from an external file.
+    pub external: bool,
+    /// The file name of an external track.
+    pub external_filename: Option<String>,
+    pub selected: bool,
+    pub demux_width: Option<i64>,
+    pub demux_height: Option<i64>,
+    pub demux_fps: Option<f64>,
+    pub demux_channel_count: Option<i64>,
+    pub demux_samplerate: Option<i64>,
+}
+
+impl Track {
+    /// Decode an entry of `track-list` read as a node.
+    pub fn from_node(node: &MpvNode) -> Result<Track> {
+        let string = |key| node.get(key).and_then(MpvNode::str).map(str::to_owned);
+        let int = |key| node.get(key).and_then(MpvNode::i64);
+        let flag = |key| node.get(key).and_then(MpvNode::bool).unwrap_or(false);
+
+        Ok(Track {
+            id: int("id").ok_or_else(|| Error::Conversion(String::from("track without an id")))?,
+            kind: string("type")
+                .ok_or_else(|| Error::Conversion(String::from("track without a type")))?
+                .parse()?,
+            src_id: int("src-id"),
+            title: string("title"),
+            lang: string("lang"),
+            codec: string("codec"),
+            decoder_desc: string("decoder-desc"),
+            default: flag("default"),
+            forced: flag("forced"),
+            hearing_impaired: flag("hearing-impaired"),
+            visual_impaired: flag("visual-impaired"),
+            image: flag("image"),
+            albumart: flag("albumart"),
+            external: flag("external"),
+            external_filename: string("external-filename"),
+            selected: flag("selected"),
+            demux_width: int("demux-w"),
+            demux_height: int("demux-h"),
+            demux_fps: node.get("demux-fps").and_then(MpvNode::f64),
+            demux_channel_count: int("demux-channel-count"),
+            demux_samplerate: int("demux-samplerate"),
+        })
+    }
+}
+
+/// A track selection property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrackSlot {
+    /// `vid`
+    Video,
+    /// `aid`
+    Audio,
+    /// `sid`
+    Sub,
+    /// `secondary-sid`
+    SecondarySub,
+}
+
+impl TrackSlot {
+    pub fn property(&self) -> &'static str {
+        match *self {
+            TrackSlot::Video => "vid",
+            TrackSlot::Audio => "aid",
+            TrackSlot::Sub => "sid",
+            TrackSlot::SecondarySub => "secondary-sid",
+        }
+    }
+
+    /// The type of the tracks selected by this property.
+    pub fn kind(&self) -> TrackType {
+        match *self {
+            TrackSlot::Video => TrackType::Video,
+            TrackSlot::Audio => TrackType::Audio,
+            TrackSlot::Sub | TrackSlot::SecondarySub => TrackType::Sub,
+        }
+    }
+}
+
+/// The value of a track selection property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrackSelection {
+    /// The track with this id.
+    Id(i64),
+    /// No track.
+    No,
+    /// Let mpv pick a track, according to options like `alang` and `slang`.
+    Auto,
+}
+
+impl fmt::Display for TrackSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrackSelection::Id(id) => write!(f, "{}", id),
+            TrackSelection::No => f.write_str("no"),
+            TrackSelection::Auto => f.write_str("auto"),
+        }
+    }
+}
+
+impl FromStr for TrackSelection {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<TrackSelection> {
+        match s {
+            "no" => Ok(TrackSelection::No),
+            "auto" => Ok(TrackSelection::Auto),
+            _ => s
+                .parse()
+                .map(TrackSelection::Id)
+                .map_err(|_| Error::Conversion(format!("invalid track selection `{}`", s))),
+        }
+    }
+}
+
+/// The tracks of an `Mpv` instance, returned by [`Mpv::tracks`].
+#[derive(Clone, Copy)]
+pub struct Tracks<'a> {
+    mpv: &'a Mpv,
+}
+
+impl Mpv {
+    pub fn tracks(&self) -> Tracks<'_> {
+        Tracks { mpv: self }
+    }
+}
+
+impl Tracks<'_> {
+    /// All tracks of the current file.
+    pub fn list(&self) -> Result<Vec<Track>> {
+        self.mpv
+            .get_property::<MpvNode>("track-list")?
+            .array()
+            .ok_or_else(|| Error::Conversion(String::from("track-list is not an array")))?
+            .iter()
+            .map(Track::from_node)
+            .collect()
+    }
+
+    /// The tracks of the given type.
+    pub fn of_type(&self, kind: TrackType) -> Result<Vec<Track>> {
+        let mut tracks = self.list()?;
+        tracks.retain(|track| track.kind == kind);
+        Ok(tracks)
+    }
+
+    /// The track selected in `slot`, if any.
+    pub fn selected(&self, slot: TrackSlot) -> Result<Option<Track>> {
+        match self.selection(slot)? {
+            TrackSelection::Id(id) => Ok(self
+                .of_type(slot.kind())?
+                .into_iter()
+                .find(|track| track.id == id)),
+            _ => Ok(None),
+        }
+    }
+
+    /// The value of the selection property of `slot`. While a file is loaded,
+    /// `auto` is replaced by the track it picked.
+    pub fn selection(&self, slot: TrackSlot) -> Result<TrackSelection> {
+        self.mpv.get_property::<String>(slot.property())?.parse()
+    }
+
+    pub fn select(&self, slot: TrackSlot, selection: TrackSelection) -> Result<()> {
+        self.mpv
+            .set_property(slot.property(), selection.to_string())
+    }
+
+    fn ids(&self, kind: TrackType) -> Result<Vec<i64>> {
+        Ok(self.of_type(kind)?.iter().map(|track| track.id).collect())
+    }
+
+    // mpv doesn't report the id of added tracks, so look for the track that
+    // wasn't in the list before.
+    fn new_id(&self, kind: TrackType, before: &[i64]) -> Result<Option<i64>> {
+        Ok(self.ids(kind)?.into_iter().find(|id| !before.contains(id)))
+    }
+
+    /// Load an external track and return its id. With
+    /// [`TrackAddFlag::Cached`](crate::commands::TrackAddFlag::Cached), this
+    /// may be the id of a track added before.
+    pub fn add(&self, command: &TrackAdd) -> Result<i64> {
+        let before = self.ids(command.kind)?;
+        self.mpv.execute(command)?;
+        if let Some(id) = self.new_id(command.kind, &before)? {
+            return Ok(id);
+        }
+        self.of_type(command.kind)?
+            .into_iter()
+            .find(|track| track.external_filename.as_deref() == Some(&command.url[..]))
+            .map(|track| track.id)
+            .ok_or(Error::Raw(mpv_error::Generic))
+    }
+
+    /// Remove the external track `id` of type `kind`.
+    pub fn remove(&self, kind: TrackType, id: i64) -> Result<()> {
+        self.mpv.execute(&TrackRemove { kind, id: Some(id) })
+    }
+
+    /// Reload the external track `id` of type `kind`, and return its new id.
+    pub fn reload(&self, kind: TrackType, id: i64) -> Result<i64> {
+        let before = self.ids(kind)?;
+        self.mpv.execute(&TrackReload { kind, id: Some(id) })?;
+        match self.new_id(kind, &before)? {
+            Some(new) => Ok(new),
+            None if self.ids(kind)?.contains(&id) => Ok(id),
+            None => Err(Error::Raw(mpv_error::Generic)),
+        }
+    }
+}
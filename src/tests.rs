@@ -2,7 +2,7 @@ use crate::events::{Event, PropertyData};
 use crate::*;
 
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[test]
 fn initializer() {
@@ -17,7 +17,7 @@ fn initializer() {
 
     assert_eq!(true, mpv.get_property("osc").unwrap());
     assert_eq!(true, mpv.get_property("input-default-bindings").unwrap());
-    assert_eq!(30i64, mpv.get_property("volume").unwrap());
+    assert_eq!(30., mpv.get(properties::VOLUME).unwrap());
 }
 
 #[test]
@@ -101,7 +101,13 @@ fn events() {
     assert!(mpv.wait_event(3.).is_none());
     mpv.command("loadfile", &["test-data/jellyfish.mp4", "append-play"])
         .unwrap();
-    assert_event_occurs!(mpv, 10., Ok(Event::StartFile));
+    assert_event_occurs!(
+        mpv,
+        10.,
+        Ok(Event::StartFile {
+            playlist_entry_id: 1
+        })
+    );
     assert_event_occurs!(
         mpv,
         10.,
@@ -122,8 +128,22 @@ fn events() {
     assert_event_occurs!(mpv, 3., Ok(Event::VideoReconfig));
     assert_event_occurs!(mpv, 3., Ok(Event::AudioReconfig));
     assert_event_occurs!(mpv, 3., Ok(Event::VideoReconfig));
-    assert_event_occurs!(mpv, 3., Ok(Event::EndFile(mpv_end_file_reason::Stop)));
-    assert_event_occurs!(mpv, 3., Ok(Event::StartFile));
+    assert_event_occurs!(
+        mpv,
+        3.,
+        Ok(Event::EndFile {
+            reason: mpv_end_file_reason::Stop,
+            playlist_entry_id: 1,
+            error: None,
+        })
+    );
+    assert_event_occurs!(
+        mpv,
+        3.,
+        Ok(Event::StartFile {
+            playlist_entry_id: 2
+        })
+    );
     assert_event_occurs!(
         mpv,
         3.,
@@ -141,7 +161,15 @@ fn events() {
     assert_event_occurs!(mpv, 3., Ok(Event::AudioReconfig));
     assert_event_occurs!(mpv, 3., Ok(Event::PlaybackRestart));
     assert_event_occurs!(mpv, 3., Ok(Event::AudioReconfig));
-    assert_event_occurs!(mpv, 10., Ok(Event::EndFile(mpv_end_file_reason::Eof)));
+    assert_event_occurs!(
+        mpv,
+        10.,
+        Ok(Event::EndFile {
+            reason: mpv_end_file_reason::Eof,
+            playlist_entry_id: 2,
+            error: None,
+        })
+    );
     assert_event_occurs!(mpv, 3., Ok(Event::AudioReconfig));
     assert!(mpv.wait_event(3.).is_none());
 }
@@ -154,5 +182,1123 @@ fn config_file() {
         Ok(())
     })
     .unwrap();
-    assert_eq!(mpv.get_property::<i64>("volume").unwrap(), 50);
+    assert_eq!(mpv.get(properties::VOLUME).unwrap(), 50.);
+}
+
+#[test]
+fn frame_png() {
+    let mut frame = frame::Frame::new(3, 2, frame::PixelFormat::Bgr0);
+    frame.data[..4].copy_from_slice(&[0x10, 0x20, 0x30, 0xff]);
+    assert_eq!(frame.pixel(0, 0), [0x30, 0x20, 0x10]);
+    assert_eq!(&frame.to_rgb24()[..6], &[0x30, 0x20, 0x10, 0, 0, 0]);
+
+    let png = frame.encode_png();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+
+    let mut other = frame.clone();
+    other.data[3] = 0;
+    assert_eq!(frame.hash(), other.hash());
+    other.data[0] = 0;
+    assert_ne!(frame.hash(), other.hash());
+}
+
+#[cfg(feature = "render")]
+#[test]
+fn capture() {
+    use crate::capture::{CaptureSink, FrameCapture};
+
+    let mpv = Mpv::with_initializer(|init| {
+        init.set_property("ao", "null")?;
+        init.set_property("pause", true)?;
+        Ok(())
+    })
+    .unwrap();
+    let mut capture = FrameCapture::new(&mpv, 64, 48, CaptureSink::Discard)
+        .unwrap()
+        .hashing(true);
+    mpv.command(
+        "loadfile",
+        &["av://lavfi:testsrc=duration=1:size=64x48", "replace"],
+    )
+    .unwrap();
+    thread::sleep(Duration::from_millis(500));
+
+    let first = capture.capture().unwrap();
+    let second = capture.capture().unwrap();
+    assert_eq!(first.index, 0);
+    assert_eq!(second.index, 1);
+    assert_eq!(first.hash, second.hash);
+    assert!(capture.frame().mean_luma() > 0.);
+    assert_eq!(capture.finish().unwrap().len(), 2);
+}
+
+#[cfg(feature = "render")]
+#[test]
+fn compositor() {
+    use crate::compositor::{Compositor, Rect};
+
+    let mut compositor = Compositor::new(128, 48);
+    for rect in Rect::grid(128, 48, 2, 1) {
+        let mpv = Mpv::with_initializer(|init| {
+            init.set_property("ao", "null")?;
+            init.set_property("pause", true)?;
+            Ok(())
+        })
+        .unwrap();
+        let index = compositor.add(mpv, rect).unwrap();
+        compositor
+            .mpv(index)
+            .unwrap()
+            .command(
+                "loadfile",
+                &["av://lavfi:testsrc=duration=1:size=64x48", "replace"],
+            )
+            .unwrap();
+    }
+    assert!(
+        compositor
+            .add(
+                Mpv::new().unwrap(),
+                Rect {
+                    x: 100,
+                    y: 0,
+                    width: 64,
+                    height: 48
+                }
+            )
+            .is_err()
+    );
+    thread::sleep(Duration::from_millis(500));
+
+    compositor.render_all().unwrap();
+    let canvas = compositor.canvas();
+    assert_eq!(canvas.pixel(10, 10), canvas.pixel(74, 10));
+}
+
+// Serializes tests that install the global callback error handler.
+static CALLBACK_HANDLER: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[test]
+fn callback_panic() {
+    use std::sync::{Arc, Mutex};
+
+    let _lock = CALLBACK_HANDLER.lock().unwrap();
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let handler_errors = errors.clone();
+    set_callback_error_handler(move |err| {
+        handler_errors.lock().unwrap().push(err.clone());
+    });
+
+    let mut mpv = Mpv::new().unwrap();
+    mpv.set_wakeup_callback(|| panic!("wakeup"));
+    mpv.observe_property("volume", Format::Int64, 0).unwrap();
+    mpv.set_property("volume", 0).unwrap();
+    thread::sleep(Duration::from_millis(250));
+    clear_callback_error_handler();
+
+    assert!(errors.lock().unwrap().contains(&CallbackError {
+        callback: Callback::Wakeup,
+        message: String::from("wakeup"),
+    }));
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn callback_reentrancy() {
+    use std::sync::{Arc, Mutex};
+
+    let _lock = CALLBACK_HANDLER.lock().unwrap();
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let handler_errors = errors.clone();
+    set_callback_error_handler(move |err| {
+        handler_errors.lock().unwrap().push(err.clone());
+    });
+
+    let mut mpv = Mpv::new().unwrap();
+    let client = mpv.create_client(None).unwrap();
+    mpv.set_wakeup_callback(move || {
+        let _ = client.get_property::<f64>("volume");
+    });
+    mpv.observe_property("volume", Format::Double, 0).unwrap();
+    mpv.set_property("volume", 0).unwrap();
+    thread::sleep(Duration::from_millis(250));
+    clear_callback_error_handler();
+
+    assert!(errors.lock().unwrap().iter().any(|err| {
+        err.callback == Callback::Wakeup
+            && err
+                .message
+                .starts_with("Mpv::get_property called from inside")
+    }));
+}
+
+#[test]
+fn typed_properties() {
+    let mut mpv = Mpv::new().unwrap();
+    mpv.set(properties::VOLUME, 42.).unwrap();
+    mpv.set(properties::PAUSE, true).unwrap();
+    assert_eq!(mpv.get(properties::VOLUME).unwrap(), 42.);
+    assert!(mpv.get(properties::PAUSE).unwrap());
+
+    mpv.observe(properties::VOLUME, 7).unwrap();
+    mpv.set(properties::VOLUME, 10.).unwrap();
+    loop {
+        let event = mpv.wait_event(3.).unwrap().unwrap();
+        if properties::VOLUME.parse(&event) == Some(10.) {
+            break;
+        }
+    }
+}
+
+#[test]
+fn node_properties() {
+    use crate::mpv_node::MpvNode;
+
+    let mpv = Mpv::new().unwrap();
+    mpv.set_property("volume", MpvNode::Double(20.)).unwrap();
+    assert_eq!(
+        mpv.get_property::<MpvNode>("volume").unwrap().f64(),
+        Some(20.)
+    );
+
+    let list = MpvNode::from(vec!["a", "b"]);
+    mpv.set_property("alang", list.clone()).unwrap();
+    assert_eq!(mpv.get_property::<MpvNode>("alang").unwrap(), list);
+
+    let version = mpv
+        .command_node(&MpvNode::from_map([
+            ("name", "expand-text"),
+            ("text", "${volume}"),
+        ]))
+        .unwrap();
+    assert_eq!(version.str(), Some("20.000000"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn node_serde() {
+    use crate::mpv_node::{MpvNode, from_node, to_node};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    enum Kind {
+        Audio,
+        Video { codec: String },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Entry {
+        id: i64,
+        title: Option<String>,
+        kinds: Vec<Kind>,
+        scale: f64,
+    }
+
+    let entry = Entry {
+        id: 1,
+        title: None,
+        kinds: vec![
+            Kind::Audio,
+            Kind::Video {
+                codec: "h264".into(),
+            },
+        ],
+        scale: 2.,
+    };
+    let node = to_node(&entry).unwrap();
+    assert_eq!(node.get("id"), Some(&MpvNode::Int64(1)));
+    assert_eq!(node.get("title"), Some(&MpvNode::None));
+    assert_eq!(from_node::<Entry>(node).unwrap(), entry);
+
+    // integer nodes deserialize into floats
+    let node = MpvNode::from_map([
+        ("id", MpvNode::Int64(2)),
+        ("kinds", MpvNode::Array(vec![])),
+        ("scale", MpvNode::Int64(3)),
+    ]);
+    assert_eq!(from_node::<Entry>(node).unwrap().scale, 3.);
+
+    struct Bytes(&'static [u8]);
+    impl Serialize for Bytes {
+        fn serialize<S: serde::Serializer>(
+            &self,
+            serializer: S,
+        ) -> std::result::Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+    assert_eq!(
+        to_node(&Bytes(&[1, 2])).unwrap(),
+        MpvNode::ByteArray(vec![1, 2])
+    );
+
+    let mpv = Mpv::new().unwrap();
+    mpv.set_property_from("alang", &["en", "de"]).unwrap();
+    let alang: Vec<String> = mpv.get_property_as("alang").unwrap();
+    assert_eq!(alang, ["en", "de"]);
+}
+
+#[test]
+fn commands() {
+    use crate::commands::*;
+    use crate::mpv_node::MpvNode;
+
+    let node = Seek::absolute(5.).precision(SeekPrecision::Exact).to_node();
+    assert_eq!(
+        node.get("flags").and_then(MpvNode::str),
+        Some("absolute+exact")
+    );
+
+    let mpv = Mpv::new().unwrap();
+    for i in 0..3 {
+        mpv.execute(
+            &LoadFile::new(format!("av://lavfi:testsrc=duration={}", i + 1))
+                .mode(LoadFileMode::Append)
+                .option("start", "0.5"),
+        )
+        .unwrap();
+    }
+    assert_eq!(mpv.get(properties::PLAYLIST_COUNT).unwrap(), 3);
+
+    mpv.execute(&PlaylistMove { from: 2, to: 0 }).unwrap();
+    assert_eq!(
+        mpv.get_property::<String>("playlist/0/filename").unwrap(),
+        "av://lavfi:testsrc=duration=3"
+    );
+    mpv.execute(&PlaylistRemove(PlaylistIndex::Index(1)))
+        .unwrap();
+    assert_eq!(mpv.get(properties::PLAYLIST_COUNT).unwrap(), 2);
+
+    mpv.execute(&Set::new("volume", "30")).unwrap();
+    mpv.execute(&Add::new("volume", 12.)).unwrap();
+    assert_eq!(mpv.get(properties::VOLUME).unwrap(), 42.);
+    mpv.execute(&Cycle::new("mute")).unwrap();
+    assert!(mpv.get(properties::MUTE).unwrap());
+
+    mpv.execute(&Stop::default()).unwrap();
+    assert_eq!(mpv.get(properties::PLAYLIST_COUNT).unwrap(), 0);
+}
+
+#[test]
+fn playlist() {
+    use crate::playlist::PlaylistMirror;
+
+    let mut mpv = Mpv::new().unwrap();
+    mpv.set_property("vo", "null").unwrap();
+    mpv.set_property("ao", "null").unwrap();
+    let mut mirror = PlaylistMirror::observe(&mpv, 3).unwrap();
+
+    let playlist = mpv.playlist();
+    let a = playlist.append("av://lavfi:testsrc=duration=5").unwrap();
+    let b = playlist.append("av://lavfi:mandelbrot=duration=5").unwrap();
+    assert_ne!(a, b);
+    assert_eq!(playlist.move_entry(1, 0).unwrap(), b);
+    let ids: Vec<_> = playlist.entries().unwrap().iter().map(|e| e.id).collect();
+    assert_eq!(ids, [b, a]);
+
+    playlist.play(1).unwrap();
+    loop {
+        let event = mpv.wait_event(10.).unwrap().unwrap();
+        mirror.handle_event(&event);
+        if let Event::StartFile { playlist_entry_id } = event {
+            assert_eq!(playlist_entry_id, a);
+            break;
+        }
+    }
+    assert_eq!(mirror.playing_id(), Some(a));
+
+    assert_eq!(mpv.playlist().remove(0).unwrap(), b);
+    while mirror.entries().len() != 1 {
+        let event = mpv.wait_event(3.).unwrap().unwrap();
+        mirror.handle_event(&event);
+    }
+    assert_eq!(mirror.current().map(|e| e.id), Some(a));
+    assert_eq!(mirror.playing().map(|e| e.id), Some(a));
+
+    // A file that fails to load ends the entry too.
+    let missing = mpv.playlist().append("test-data/missing.mp4").unwrap();
+    mpv.playlist().play(1).unwrap();
+    loop {
+        let event = mpv.wait_event(10.).unwrap().unwrap();
+        let changed = mirror.handle_event(&event);
+        if let Event::EndFile {
+            playlist_entry_id,
+            error,
+            ..
+        } = event
+            && playlist_entry_id == missing
+        {
+            assert!(error.is_some());
+            assert!(changed);
+            break;
+        }
+    }
+    assert_eq!(mirror.playing_id(), None);
+}
+
+#[test]
+fn tracks() {
+    use crate::commands::TrackAdd;
+    use crate::tracks::{TrackSelection, TrackSlot, TrackType};
+
+    let subs = std::env::temp_dir().join("libmpv2-tracks.srt");
+    std::fs::write(&subs, "1\n00:00:00,000 --> 00:00:05,000\nHello\n").unwrap();
+
+    let mut mpv = Mpv::new().unwrap();
+    mpv.set_property("vo", "null").unwrap();
+    mpv.set_property("ao", "null").unwrap();
+    mpv.set_property("pause", true).unwrap();
+    mpv.command("loadfile", &["test-data/speech_12kbps_mb.wav"])
+        .unwrap();
+    assert_event_occurs!(mpv, 10., Ok(Event::FileLoaded));
+
+    let tracks = mpv.tracks();
+    let audio = tracks.of_type(TrackType::Audio).unwrap();
+    assert_eq!(audio.len(), 1);
+    assert!(audio[0].selected);
+    assert_eq!(
+        tracks.selection(TrackSlot::Audio).unwrap(),
+        TrackSelection::Id(audio[0].id)
+    );
+
+    let path = subs.to_str().unwrap();
+    let id = tracks
+        .add(&TrackAdd::sub(path).lang("en").title("Test"))
+        .unwrap();
+    let sub = tracks.selected(TrackSlot::Sub).unwrap().unwrap();
+    assert_eq!(sub.id, id);
+    assert!(sub.external);
+    assert_eq!(sub.external_filename.as_deref(), Some(path));
+    assert_eq!(sub.lang.as_deref(), Some("en"));
+
+    tracks.select(TrackSlot::Sub, TrackSelection::No).unwrap();
+    assert_eq!(tracks.selected(TrackSlot::Sub).unwrap(), None);
+
+    let reloaded = tracks.reload(TrackType::Sub, id).unwrap();
+    tracks.remove(TrackType::Sub, reloaded).unwrap();
+    assert!(tracks.of_type(TrackType::Sub).unwrap().is_empty());
+}
+
+#[test]
+fn track_policy() {
+    use crate::track_policy::*;
+    use crate::tracks::{Track, TrackSelection, TrackType};
+
+    let track = |id, kind, lang: &str, title: &str| Track {
+        id,
+        kind,
+        src_id: None,
+        title: Some(title.into()).filter(|t: &String| !t.is_empty()),
+        lang: Some(lang.into()),
+        codec: None,
+        decoder_desc: None,
+        default: false,
+        forced: false,
+        hearing_impaired: false,
+        visual_impaired: false,
+        image: false,
+        albumart: false,
+        external: false,
+        external_filename: None,
+        selected: false,
+        demux_width: None,
+        demux_height: None,
+        demux_fps: None,
+        demux_channel_count: None,
+        demux_samplerate: None,
+    };
+    let mut tracks = vec![
+        track(1, TrackType::Audio, "en", "Director's Commentary"),
+        track(2, TrackType::Audio, "en", ""),
+        track(3, TrackType::Audio, "ja", ""),
+        track(1, TrackType::Sub, "en-US", "SDH"),
+        track(2, TrackType::Sub, "en", "Signs"),
+        track(3, TrackType::Sub, "en", ""),
+    ];
+    tracks[3].hearing_impaired = true;
+    tracks[4].forced = true;
+
+    let mut policy = PreferencePolicy {
+        audio_languages: vec!["de".into(), "en".into()],
+        sub_languages: vec!["en".into()],
+        subtitles: SubtitleMode::ForcedOnly,
+        ..PreferencePolicy::default()
+    };
+    let choice = policy.choose(&tracks);
+    assert_eq!(choice.audio, Some(TrackSelection::Id(2)));
+    assert_eq!(choice.sub, Some(TrackSelection::Id(2)));
+
+    policy.subtitles = SubtitleMode::Always;
+    policy.hearing_impaired = Preference::Prefer;
+    assert_eq!(policy.choose(&tracks).sub, Some(TrackSelection::Id(1)));
+    policy.hearing_impaired = Preference::Avoid;
+    assert_eq!(policy.choose(&tracks).sub, Some(TrackSelection::Id(2)));
+
+    let mut mpv = Mpv::new().unwrap();
+    mpv.set_property("vo", "null").unwrap();
+    mpv.set_property("ao", "null").unwrap();
+    let hook = TrackPolicyHook::register(&mpv, policy, 5).unwrap();
+    mpv.command("loadfile", &["test-data/speech_12kbps_mb.wav"])
+        .unwrap();
+    loop {
+        let event = mpv.wait_event(10.).unwrap().unwrap();
+        if let Some(id) = hook.matches(&event) {
+            let choice = hook.run(&mpv, id).unwrap();
+            assert_eq!(choice.audio, Some(TrackSelection::Id(1)));
+            assert_eq!(choice.sub, Some(TrackSelection::No));
+            break;
+        }
+    }
+    assert_event_occurs!(mpv, 10., Ok(Event::FileLoaded));
+}
+
+#[test]
+fn chapters() {
+    use crate::chapters::{ChapterChange, ChapterObserver};
+
+    let metadata = std::env::temp_dir().join("libmpv2-chapters.txt");
+    std::fs::write(
+        &metadata,
+        ";FFMETADATA1\n\
+         [CHAPTER]\nTIMEBASE=1/1000\nSTART=0\nEND=2000\ntitle=Intro\n\
+         [CHAPTER]\nTIMEBASE=1/1000\nSTART=2000\nEND=4000\ntitle=Outro\n",
+    )
+    .unwrap();
+
+    let mut mpv = Mpv::new().unwrap();
+    mpv.set_property("vo", "null").unwrap();
+    mpv.set_property("pause", true).unwrap();
+    mpv.set_property("chapters-file", metadata.to_str().unwrap())
+        .unwrap();
+    let observer = ChapterObserver::observe(&mpv, 4).unwrap();
+    mpv.command("loadfile", &["av://lavfi:testsrc=duration=4"])
+        .unwrap();
+    loop {
+        let event = mpv.wait_event(10.).unwrap().unwrap();
+        if let Some(ChapterChange::List(list)) = observer.parse(&event)
+            && !list.is_empty()
+        {
+            assert_eq!(list[1].title.as_deref(), Some("Outro"));
+            assert_eq!(list[1].time, 2.);
+            break;
+        }
+    }
+
+    let chapters = mpv.chapters();
+    assert_eq!(chapters.list().unwrap().len(), 2);
+    assert_eq!(chapters.current().unwrap(), Some(0));
+    chapters.next().unwrap();
+    loop {
+        let event = mpv.wait_event(3.).unwrap().unwrap();
+        if observer.parse(&event) == Some(ChapterChange::Current(Some(1))) {
+            break;
+        }
+    }
+    let chapters = mpv.chapters();
+    chapters.set(0).unwrap();
+    assert_eq!(chapters.current().unwrap(), Some(0));
+    assert!(chapters.editions().unwrap().is_empty());
+}
+
+#[test]
+fn chapter_authoring() {
+    use crate::chapters::{Chapter, to_ffmetadata, write_ffmetadata};
+
+    let chapter = |time, title: &str| Chapter {
+        title: Some(title.into()),
+        time,
+    };
+    let chapters = [chapter(0., "A=B; #1"), chapter(1.5, "Two")];
+    assert!(to_ffmetadata(&chapters, Some(3.)).contains("END=1500\ntitle=A\\=B\\; \\#1\n"));
+
+    let mut mpv = Mpv::new().unwrap();
+    mpv.set_property("vo", "null").unwrap();
+    mpv.set_property("pause", true).unwrap();
+    let path = std::env::temp_dir().join("libmpv2-chapter-authoring.txt");
+    write_ffmetadata(&path, &chapters, Some(3.)).unwrap();
+    mpv.chapters().set_chapters_file(&path).unwrap();
+    mpv.command("loadfile", &["av://lavfi:testsrc=duration=3"])
+        .unwrap();
+    assert_event_occurs!(mpv, 10., Ok(Event::FileLoaded));
+
+    let chapters_api = mpv.chapters();
+    assert_eq!(chapters_api.list().unwrap(), chapters);
+
+    chapters_api
+        .replace(&[chapter(2., "Late"), chapter(0.5, "Early")])
+        .unwrap();
+    chapters_api.extend(&[chapter(1., "Middle")]).unwrap();
+    assert_eq!(
+        chapters_api.list().unwrap(),
+        [
+            chapter(0.5, "Early"),
+            chapter(1., "Middle"),
+            chapter(2., "Late")
+        ]
+    );
+}
+
+#[test]
+fn metadata() {
+    use crate::metadata::{IcyWatcher, Metadata, MetadataSource};
+    use crate::mpv_node::MpvNode;
+
+    let node = MpvNode::from_map([
+        ("TITLE", "Song"),
+        ("Artist", "Band"),
+        ("icy-title", "Band - Song"),
+    ]);
+    let metadata = Metadata::from_node(&node).unwrap();
+    assert_eq!(metadata.title(), Some("Song"));
+    assert_eq!(metadata.artist(), Some("Band"));
+    assert_eq!(metadata.icy_title(), Some("Band - Song"));
+    assert_eq!(metadata.iter().next(), Some(("TITLE", "Song")));
+
+    let mut mpv = Mpv::new().unwrap();
+    mpv.set_property("vo", "null").unwrap();
+    mpv.set_property("ao", "null").unwrap();
+    let mut icy = IcyWatcher::observe(&mpv, 6).unwrap();
+    let change = |node| Event::PropertyChange {
+        name: "metadata",
+        change: PropertyData::Node(node),
+        reply_userdata: 6,
+    };
+    assert!(icy.handle_event(&change(node.clone())));
+    assert!(!icy.handle_event(&change(node)));
+    assert_eq!(icy.title(), Some("Band - Song"));
+
+    mpv.command("loadfile", &["test-data/speech_12kbps_mb.wav"])
+        .unwrap();
+    assert_event_occurs!(mpv, 10., Ok(Event::FileLoaded));
+    mpv.metadata(&MetadataSource::File).unwrap();
+    mpv.metadata(&MetadataSource::Filtered).unwrap();
+}
+
+#[test]
+fn probe() {
+    use crate::probe::{ProbeOptions, probe, probe_on, probe_with};
+    use crate::tracks::TrackType;
+    use std::time::Duration;
+
+    let info = probe("test-data/speech_12kbps_mb.wav").unwrap();
+    assert_eq!(info.container.as_deref(), Some("wav"));
+    assert!(info.duration.unwrap() > 0.);
+    assert!(info.video().is_none());
+    let (channels, samplerate) = info.audio_format().unwrap();
+    assert_eq!((channels, samplerate), (1, 48000));
+    assert_eq!(info.tracks_of(TrackType::Audio).count(), 1);
+
+    let info = probe("av://lavfi:testsrc=size=64x48:rate=25").unwrap();
+    assert_eq!(info.resolution(), Some((64, 48)));
+    assert_eq!(info.frame_rate(), Some(25.));
+
+    let missing = probe("test-data/missing.mkv");
+    assert!(matches!(missing, Err(Error::Loadfile { .. })));
+    let timeout = probe_with(
+        "test-data/speech_12kbps_mb.wav",
+        &ProbeOptions::default().timeout(Duration::ZERO),
+    );
+    assert_eq!(timeout, Err(Error::Timeout));
+
+    // The file loaded by the first probe is replaced by the second.
+    let mut mpv = Mpv::with_initializer(|init| {
+        init.set_property("vo", "null")?;
+        init.set_property("ao", "null")?;
+        Ok(())
+    })
+    .unwrap();
+    for duration in [1., 2.] {
+        let path = format!("av://lavfi:testsrc=duration={}", duration);
+        let info = probe_on(&mut mpv, &path, Duration::from_secs(10)).unwrap();
+        assert_eq!(info.duration, Some(duration));
+    }
+}
+
+#[test]
+fn pool() {
+    use crate::pool::MpvPool;
+    use crate::probe::probe_on;
+    use std::time::Duration;
+
+    assert_eq!(
+        MpvPool::new(0).err(),
+        Some(Error::Raw(mpv_error::InvalidParameter))
+    );
+    let pool = MpvPool::new(2).unwrap();
+    assert_eq!(pool.idle(), 2);
+
+    let durations: Vec<_> = thread::scope(|scope| {
+        let jobs: Vec<_> = (1..=4)
+            .map(|i| {
+                let pool = &pool;
+                scope.spawn(move || {
+                    let path = format!("av://lavfi:testsrc=duration={}", i);
+                    pool.run(|mpv| probe_on(mpv, &path, Duration::from_secs(10)))
+                        .ok()
+                        .and_then(|info| info.duration)
+                })
+            })
+            .collect();
+        jobs.into_iter().map(|job| job.join().unwrap()).collect()
+    });
+    assert_eq!(durations, [Some(1.), Some(2.), Some(3.), Some(4.)]);
+    assert_eq!(pool.idle(), 2);
+
+    {
+        let mut mpv = pool.get().unwrap();
+        mpv.set_property("volume", 10.).unwrap();
+        let _other = pool.get().unwrap();
+        assert_eq!(
+            pool.get_timeout(Duration::from_millis(10)).err(),
+            Some(Error::Timeout)
+        );
+        mpv.discard();
+    }
+    assert_eq!(pool.idle(), 1);
+    let a = pool.get().unwrap();
+    let b = pool.get().unwrap();
+    assert_eq!(a.get_property::<f64>("volume").unwrap(), 100.);
+    assert_eq!(b.get_property::<f64>("volume").unwrap(), 100.);
+}
+
+#[cfg(feature = "render")]
+#[test]
+fn thumbnails() {
+    use crate::frame::Frame;
+    use crate::thumbnails::{SpriteSheet, ThumbnailGenerator};
+
+    let thumbnails = ThumbnailGenerator::new(4, 32)
+        .generate("av://lavfi:testsrc=duration=4:size=64x48")
+        .unwrap();
+    assert_eq!(thumbnails.duration, 4.);
+    let times: Vec<_> = thumbnails.thumbnails.iter().map(|t| t.time).collect();
+    assert_eq!(times.len(), 4);
+    assert!(times.windows(2).all(|w| w[0] < w[1]));
+    let frame = &thumbnails.thumbnails[0].frame;
+    assert_eq!((frame.width, frame.height), (32, 24));
+    assert!(frame.mean_luma() > 16.);
+
+    let decoded = Frame::decode_ppm(&frame.encode_ppm()).unwrap();
+    assert_eq!(decoded.to_rgb24(), frame.to_rgb24());
+    assert!(Frame::decode_ppm(b"P6\n1 1\n255\n").is_err());
+    assert!(Frame::decode_ppm(b"P6\n4294967296 4294967296\n255\n").is_err());
+
+    let mut canvas = Frame::new(8, 8, frame.format);
+    canvas.blit(frame, 8, 0);
+    canvas.blit(frame, 0, 8);
+    assert!(canvas.data.iter().all(|&b| b == 0));
+
+    let sheet = SpriteSheet::new(&thumbnails, 3);
+    assert_eq!((sheet.image.width, sheet.image.height), (96, 48));
+    assert_eq!((sheet.tiles[3].x, sheet.tiles[3].y), (0, 24));
+    assert_eq!(
+        sheet.image.pixel(32, 0),
+        thumbnails.thumbnails[1].frame.pixel(0, 0)
+    );
+    let vtt = sheet.webvtt("sprites.png");
+    assert!(vtt.starts_with("WEBVTT\n\n00:00:00.000 --> "));
+    assert!(vtt.contains("sprites.png#xywh=32,0,32,24\n"));
+    assert!(
+        vtt.trim_end()
+            .ends_with("--> 00:00:04.000\nsprites.png#xywh=0,24,32,24")
+    );
+}
+
+#[test]
+fn screenshot() {
+    use crate::commands::ScreenshotMode;
+    use crate::frame::{Frame, PixelFormat};
+    use crate::mpv_node::MpvNode;
+
+    let node = MpvNode::from_map([
+        ("w", MpvNode::Int64(2)),
+        ("h", MpvNode::Int64(1)),
+        ("stride", MpvNode::Int64(8)),
+        ("format", MpvNode::from("bgr0")),
+        ("data", MpvNode::ByteArray(vec![3, 2, 1, 0, 6, 5, 4, 0])),
+    ]);
+    let frame = Frame::from_node(&node).unwrap();
+    assert_eq!(frame.format, PixelFormat::Bgr0);
+    assert_eq!(frame.to_rgb24(), [1, 2, 3, 4, 5, 6]);
+    let short = MpvNode::from_map([("h", MpvNode::Int64(2))]);
+    assert!(matches!(
+        Frame::from_node(&short),
+        Err(Error::Conversion(_))
+    ));
+
+    let mut mpv = Mpv::with_initializer(|init| {
+        init.set_property("vo", "null")?;
+        init.set_property("ao", "null")?;
+        init.set_property("pause", true)?;
+        Ok(())
+    })
+    .unwrap();
+    mpv.command("loadfile", &["av://lavfi:testsrc=size=64x48"])
+        .unwrap();
+    loop {
+        match mpv.wait_event(10.) {
+            Some(Ok(Event::PlaybackRestart)) => break,
+            Some(Ok(_)) => {}
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+    thread::sleep(Duration::from_millis(100));
+
+    let frame = mpv.screenshot_raw(ScreenshotMode::Video).unwrap();
+    assert_eq!((frame.width, frame.height), (64, 48));
+    assert!(frame.mean_luma() > 0.);
+
+    let path = std::env::temp_dir().join("libmpv2-screenshot.png");
+    mpv.screenshot_to_file(&path, ScreenshotMode::Subtitles)
+        .unwrap();
+    assert!(std::fs::read(&path).unwrap().starts_with(b"\x89PNG"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn encoder() {
+    use crate::encoder::{EncodeEvent, Encoder};
+    use crate::probe::probe;
+
+    let output = std::env::temp_dir().join("libmpv2-encoder.mkv");
+    let mut updates = Vec::new();
+    let summary = Encoder::new("av://lavfi:testsrc=duration=3:size=64x48", &output)
+        .video_codec("ffv1")
+        .video_option("g", "1")
+        .range(Some(1.), Some(2.))
+        .run(|progress| {
+            updates.push(*progress);
+            true
+        })
+        .unwrap();
+    assert!(!summary.cancelled);
+    assert!(summary.position >= 1.9);
+    assert!(!updates.is_empty());
+    assert!(updates.windows(2).all(|w| w[0].percent <= w[1].percent));
+    assert!(updates.iter().all(|p| (0. ..=100.).contains(&p.percent)));
+
+    let info = probe(&output).unwrap();
+    assert!((info.duration.unwrap() - 1.).abs() < 0.1);
+    assert_eq!(info.resolution(), Some((64, 48)));
+    std::fs::remove_file(&output).unwrap();
+
+    let mut encoding = Encoder::new("test-data/missing.mkv", &output)
+        .spawn()
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let result = loop {
+        assert!(Instant::now() < deadline, "encoding did not finish");
+        if let Some(EncodeEvent::Finished(result)) = encoding.wait_event(1.) {
+            break result;
+        }
+    };
+    assert!(matches!(result, Err(Error::Loadfile { .. })));
+    assert!(encoding.is_finished());
+    assert_eq!(encoding.wait_event(0.), None);
+}
+
+#[test]
+fn clip() {
+    use crate::clip::{ClipMode, ClipPreset, export_clip};
+    use crate::encoder::EncodeEvent;
+    use crate::probe::probe;
+
+    let dir = std::env::temp_dir();
+    let copy = dir.join("libmpv2-clip.wav");
+    let summary = export_clip(
+        "test-data/speech_12kbps_mb.wav",
+        2.,
+        5.,
+        &copy,
+        ClipMode::Copy,
+    )
+    .unwrap()
+    .run(|_| true)
+    .unwrap();
+    assert!(!summary.cancelled);
+    let duration = probe(&copy).unwrap().duration.unwrap();
+    // Every packet of the WAV file is a keyframe.
+    assert!((duration - 3.).abs() < 0.25, "{}", duration);
+    std::fs::remove_file(&copy).unwrap();
+
+    let reencoded = dir.join("libmpv2-clip.mkv");
+    let summary = export_clip(
+        "av://lavfi:testsrc=duration=4:size=64x48",
+        1.,
+        3.,
+        &reencoded,
+        ClipMode::Reencode(ClipPreset::Lossless),
+    )
+    .unwrap()
+    .run(|_| true)
+    .unwrap();
+    assert!((probe(&summary.output).unwrap().duration.unwrap() - 2.).abs() < 0.25);
+
+    let mut export = export_clip(
+        "av://lavfi:testsrc=duration=60:size=64x48",
+        0.,
+        60.,
+        &reencoded,
+        ClipMode::Reencode(ClipPreset::Lossless),
+    )
+    .unwrap();
+    export.cancel().unwrap();
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let result = loop {
+        assert!(Instant::now() < deadline, "encoding did not finish");
+        if let Some(EncodeEvent::Finished(result)) = export.wait_event(1.) {
+            break result;
+        }
+    };
+    assert!(result.unwrap().cancelled);
+    std::fs::remove_file(&reencoded).unwrap();
+
+    assert_eq!(
+        export_clip("a.mkv", 3., 2., "b.mkv", ClipMode::Copy).err(),
+        Some(Error::Raw(mpv_error::InvalidParameter))
+    );
+
+    let mpv = Mpv::new().unwrap();
+    assert_eq!(mpv.ab_loop().unwrap(), (None, None));
+    mpv.set_ab_loop(Some(1.5), Some(4.)).unwrap();
+    assert_eq!(mpv.ab_loop().unwrap(), (Some(1.5), Some(4.)));
+}
+
+#[test]
+fn audio_tap() {
+    use crate::audio_tap::{AudioTap, AudioTapOptions};
+
+    let options = AudioTapOptions::default()
+        .channels(1)
+        .sample_rate(8000)
+        .block_frames(800)
+        .start(1.);
+    let tap = AudioTap::open("test-data/speech_12kbps_mb.wav", &options).unwrap();
+    let blocks: Vec<_> = tap.map(Result::unwrap).collect();
+    assert!(blocks.len() > 10);
+    assert!((blocks[0].time - 1.).abs() < 1e-9);
+    assert!((blocks[1].time - 1.1).abs() < 1e-9);
+    assert!(blocks[..blocks.len() - 1].iter().all(|b| b.frames() == 800));
+    let total: f64 = blocks.iter().map(|b| b.duration()).sum();
+    assert!((total - 9.8135).abs() < 0.05, "{}", total);
+    assert!(
+        blocks
+            .iter()
+            .flat_map(|b| &b.samples)
+            .all(|s| s.abs() <= 1.)
+    );
+    assert!(blocks.iter().flat_map(|b| &b.samples).any(|&s| s != 0.));
+
+    let mut missing = AudioTap::open("test-data/missing.wav", &options).unwrap();
+    assert!(matches!(missing.next(), Some(Err(Error::Loadfile { .. }))));
+    assert!(missing.next().is_none());
+}
+
+#[test]
+fn analyze_audio() {
+    use crate::analysis::analyze_audio;
+
+    let report = analyze_audio("test-data/speech_12kbps_mb.wav").unwrap();
+    assert!(report.integrated < 0. && report.integrated > -70.);
+    assert!(report.range >= 0.);
+    assert_eq!(report.channels.len(), 1);
+    let channel = report.channels[0];
+    assert!(channel.rms < channel.peak && channel.peak <= 0.);
+    assert!(report.true_peak >= channel.peak - 0.5);
+
+    let gain = report.normalization_gain(-23., -1.);
+    assert!(gain <= -1. - report.true_peak + 1e-9);
+    assert!(gain <= -23. - report.integrated + 1e-9);
+
+    let tone = analyze_audio("av://lavfi:sine=frequency=1000:duration=3").unwrap();
+    assert!((tone.integrated - tone.channels[0].rms).abs() < 3.5);
+    assert!(matches!(
+        analyze_audio("av://lavfi:testsrc=duration=1"),
+        Err(Error::Loadfile { .. })
+    ));
+}
+
+#[test]
+fn detect_scenes() {
+    use crate::analysis::{detect_black, detect_scenes};
+
+    // Black for a second, then grey, then black again from 3 seconds.
+    let source = "av://lavfi:nullsrc=s=64x48:d=4,\
+                  geq=lum='if(between(T\\,1\\,3-0.001)\\,160\\,16)':cb=128:cr=128,\
+                  format=yuv420p";
+    let scenes = detect_scenes(source, 0.3).unwrap();
+    assert_eq!(scenes.len(), 2, "{:?}", scenes);
+    assert!((scenes[0].time - 1.).abs() < 0.05);
+    assert!((scenes[1].time - 3.).abs() < 0.05);
+    assert!(scenes.iter().all(|scene| scene.score > 0.3));
+
+    let black = detect_black(source).unwrap();
+    assert_eq!(black.len(), 2, "{:?}", black);
+    assert!(black[0].start.abs() < 0.05 && (black[0].end - 1.).abs() < 0.05);
+    assert!((black[1].start - 3.).abs() < 0.05 && (black[1].end - 4.).abs() < 0.05);
+    assert!((black[0].duration() - 1.).abs() < 0.1);
+
+    assert!(matches!(
+        detect_black("test-data/speech_12kbps_mb.wav"),
+        Err(Error::Loadfile { .. })
+    ));
+}
+
+#[test]
+fn filters() {
+    use crate::filters::{Filter, FilterKind, format_chain};
+
+    let eq = Filter::new("equalizer")
+        .label("eq")
+        .param("f", "1000")
+        .param("g", "-3");
+    assert_eq!(eq.to_string(), "@eq:equalizer=f=1000:g=-3");
+    assert_eq!(
+        eq.clone().enabled(false).to_string(),
+        "@eq:!equalizer=f=1000:g=-3"
+    );
+    let graph = Filter::new("lavfi").param("graph", "[aecho=0.8:0.9:40:0.3]");
+    assert_eq!(
+        format_chain(&[Filter::new("loudnorm"), graph]),
+        "loudnorm,lavfi=graph=%22%[aecho=0.8:0.9:40:0.3]"
+    );
+
+    let mut mpv = Mpv::with_initializer(|init| {
+        init.set_property("vo", "null")?;
+        init.set_property("ao", "null")?;
+        Ok(())
+    })
+    .unwrap();
+    let volume = Filter::new("volume").label("vol").param("volume", "0.5");
+    mpv.filters(FilterKind::Audio)
+        .set(&[volume.clone(), eq.clone()])
+        .unwrap();
+    assert_eq!(
+        mpv.filters(FilterKind::Audio).list().unwrap(),
+        [volume.clone(), eq.clone()]
+    );
+
+    mpv.filters(FilterKind::Audio).toggle("eq").unwrap();
+    assert!(
+        !mpv.filters(FilterKind::Audio)
+            .get("eq")
+            .unwrap()
+            .unwrap()
+            .enabled
+    );
+    mpv.filters(FilterKind::Audio).remove("eq").unwrap();
+    assert_eq!(mpv.filters(FilterKind::Audio).list().unwrap(), [volume]);
+
+    mpv.command("loadfile", &["av://lavfi:sine=duration=10"])
+        .unwrap();
+    loop {
+        match mpv.wait_event(10.) {
+            Some(Ok(Event::PlaybackRestart)) => break,
+            Some(Ok(_)) => {}
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+    mpv.filters(FilterKind::Audio)
+        .command("vol", "volume", "0.25")
+        .unwrap();
+    assert!(
+        mpv.filters(FilterKind::Audio)
+            .command("vol", "no-such-command", "1")
+            .is_err()
+    );
+
+    mpv.filters(FilterKind::Audio).clear().unwrap();
+    assert!(mpv.filters(FilterKind::Audio).list().unwrap().is_empty());
+    assert!(mpv.filters(FilterKind::Video).list().unwrap().is_empty());
+}
+
+#[test]
+fn audio_devices() {
+    use crate::audio_devices::AudioDeviceWatcher;
+
+    let mpv = Mpv::with_initializer(|init| {
+        init.set_property("ao", "null")?;
+        Ok(())
+    })
+    .unwrap();
+    // Special outputs like `null` and `pcm` list no devices, but `auto` is
+    // always there.
+    let devices = mpv.audio_devices().unwrap();
+    assert!(devices[0].is_auto());
+
+    mpv.set_audio_device("auto").unwrap();
+    assert_eq!(mpv.audio_device().unwrap(), "auto");
+    assert_eq!(
+        mpv.set_audio_device("no-such-device"),
+        Err(Error::Raw(mpv_error::InvalidParameter))
+    );
+    assert_eq!(mpv.audio_device().unwrap(), "auto");
+
+    let watcher = AudioDeviceWatcher::observe(&mpv, 7).unwrap();
+    assert_eq!(watcher.devices(), &devices[..]);
+    assert!(!watcher.fall_back(&mpv).unwrap());
+    mpv.set_property("audio-device", "unplugged/headset")
+        .unwrap();
+    assert!(watcher.fall_back(&mpv).unwrap());
+    assert_eq!(mpv.audio_device().unwrap(), "auto");
+}
+
+#[test]
+fn cache_state() {
+    use crate::cache::{BufferingEvent, BufferingMonitor};
+
+    let mut mpv = Mpv::with_initializer(|init| {
+        init.set_property("vo", "null")?;
+        init.set_property("ao", "null")?;
+        init.set_property("pause", true)?;
+        Ok(())
+    })
+    .unwrap();
+    mpv.command("loadfile", &["test-data/jellyfish.mp4"])
+        .unwrap();
+    while !matches!(mpv.wait_event(10.).unwrap().unwrap(), Event::FileLoaded) {}
+
+    let state = mpv.cache_state().unwrap();
+    assert!(!state.underrun);
+    assert!(state.fw_bytes >= 0);
+    for range in &state.seekable_ranges {
+        assert!(range.start <= range.end);
+        assert!(state.is_cached(range.start));
+    }
+
+    // Waiting for a seek and for the cache is one buffering period.
+    let mut monitor = BufferingMonitor::observe(&mpv, 3).unwrap();
+    let change = |name, flag| Event::PropertyChange {
+        name,
+        change: PropertyData::Flag(flag),
+        reply_userdata: 3,
+    };
+    let percent = |percent| Event::PropertyChange {
+        name: "cache-buffering-state",
+        change: PropertyData::Int64(percent),
+        reply_userdata: 3,
+    };
+    assert_eq!(monitor.handle_event(&percent(100)), None);
+    assert_eq!(
+        monitor.handle_event(&change("seeking", true)),
+        Some(BufferingEvent::Started)
+    );
+    assert_eq!(
+        monitor.handle_event(&change("paused-for-cache", true)),
+        None
+    );
+    assert_eq!(
+        monitor.handle_event(&percent(40)),
+        Some(BufferingEvent::Progress(40))
+    );
+    assert_eq!(monitor.handle_event(&change("seeking", false)), None);
+    assert!(monitor.is_buffering());
+    assert_eq!(
+        monitor.handle_event(&change("paused-for-cache", false)),
+        Some(BufferingEvent::Ended)
+    );
+    assert!(!monitor.is_buffering());
 }